@@ -6,5 +6,15 @@ use serenity::framework::standard::macros::group;
 #[group]
 #[owners_only]
 #[help_available(false)]
-#[commands(addcom, color, rmcom, set_help)]
+#[commands(
+    addcom,
+    addword,
+    checklinks,
+    color,
+    endlockdown,
+    reactionrole,
+    rmcom,
+    rmword,
+    set_help
+)]
 struct Admin;
@@ -1,5 +1,8 @@
 use crate::prelude::*;
+use crate::utils::anti_raid::end_lockdown;
 use crate::utils::config::{get_conf, hot_reload_conf};
+use crate::utils::links::{check_links, format_reports};
+use crate::utils::reaction_roles::check_reaction_roles;
 
 #[derive(Debug)]
 enum CommandReloadAction {
@@ -12,6 +15,8 @@ enum AllowedReloads {
     Help,
     Color,
     Commands(CommandReloadAction),
+    Words(CommandReloadAction),
+    ReactionRoles(CommandReloadAction),
 }
 
 #[derive(Debug)]
@@ -120,6 +125,65 @@ async fn try_hot_reload(mut args: Args, discrim: AllowedReloads) -> Result<Strin
                 }
             }
         }
+        AllowedReloads::ReactionRoles(action) => {
+            let (message, emoji) = match (args.single::<String>(), args.single::<String>()) {
+                (Ok(message), Ok(emoji)) => (message, emoji),
+                _ => return Err(ImproperFormat),
+            };
+            // custom emoji arrive as `<:name:id>`, but are configured by name
+            let emoji = match serenity::utils::parse_emoji(&emoji) {
+                Some(custom) => custom.name,
+                None => emoji.trim_matches(':').to_string(),
+            };
+
+            match action {
+                CommandReloadAction::Append => match args.remains() {
+                    Some(role) => {
+                        if config
+                            .push_reaction_role(&message, &emoji, role.trim())
+                            .await
+                            .is_err()
+                        {
+                            return Err(OperationFailed);
+                        } else {
+                            success_msg =
+                                format!("{} now gives the `{}` role!", emoji, role.trim());
+                        }
+                    }
+                    None => return Err(ImproperFormat),
+                },
+                CommandReloadAction::Remove => {
+                    if config.pop_reaction_role(&message, &emoji).await.is_err() {
+                        return Err(OperationFailed);
+                    } else {
+                        success_msg = format!("{} no longer gives a role!", emoji);
+                    }
+                }
+            }
+        }
+        AllowedReloads::Words(action) => {
+            let term = match args.remains() {
+                Some(term) => term.trim(),
+                None => return Err(ImproperFormat),
+            };
+
+            match action {
+                CommandReloadAction::Append => {
+                    if config.push_filter_term(term).await.is_err() {
+                        return Err(OperationFailed);
+                    } else {
+                        success_msg = format!("added `{}` to the word filter!", term);
+                    }
+                }
+                CommandReloadAction::Remove => {
+                    if config.pop_filter_term(term).await.is_err() {
+                        return Err(OperationFailed);
+                    } else {
+                        success_msg = format!("removed `{}` from the word filter!", term);
+                    }
+                }
+            }
+        }
     }
 
     match hot_reload_conf(config_path, config).await {
@@ -169,6 +233,53 @@ async fn rmcom(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
     announce(ctx, msg, result, &CommandResponse::DmOwner).await
 }
 
+#[instrument]
+#[command]
+#[owners_only]
+#[description = "add a word, or a `/regex/`, to the word filter"]
+#[usage = "`!addword <word or phrase>` or `!addword /<regex>/`"]
+#[example = "`!addword free nitro`"]
+async fn addword(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    // the term shouldn't stay up in the channel it was added from
+    if let Err(e) = msg.delete(ctx).await {
+        info!("Unable to delete addword message: {}", e);
+    }
+    let result =
+        match try_hot_reload(args, AllowedReloads::Words(CommandReloadAction::Append)).await {
+            Ok(success_msg) => success_msg,
+            Err(e) => {
+                let description = e.to_string();
+                info!("{}", &description);
+                description
+            }
+        };
+
+    announce(ctx, msg, result, &CommandResponse::DmOwner).await
+}
+
+#[instrument]
+#[command]
+#[owners_only]
+#[description = "remove a word, or a `/regex/`, from the word filter"]
+#[usage = "`!rmword <word or phrase>` or `!rmword /<regex>/`"]
+#[example = "`!rmword free nitro`"]
+async fn rmword(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    if let Err(e) = msg.delete(ctx).await {
+        info!("Unable to delete rmword message: {}", e);
+    }
+    let result =
+        match try_hot_reload(args, AllowedReloads::Words(CommandReloadAction::Remove)).await {
+            Ok(success_msg) => success_msg,
+            Err(e) => {
+                let description = e.to_string();
+                info!("{}", &description);
+                description
+            }
+        };
+
+    announce(ctx, msg, result, &CommandResponse::DmOwner).await
+}
+
 #[instrument]
 #[command]
 #[owners_only]
@@ -206,3 +317,73 @@ async fn color(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
 
     announce(ctx, msg, result, &CommandResponse::DmOwner).await
 }
+
+#[instrument]
+#[command]
+#[owners_only]
+#[description = "check every link in the commands for redirects and errors"]
+#[usage = "`!checklinks`"]
+async fn checklinks(ctx: &Context, msg: &Message) -> CommandResult {
+    let commands = crate::CONFIG.lock().await.get_commands().clone();
+    let _ = msg.channel_id.broadcast_typing(&ctx.http).await;
+    let reports = check_links(&commands).await;
+
+    announce(
+        ctx,
+        msg,
+        format_reports(&reports),
+        &CommandResponse::DmOwner,
+    )
+    .await
+}
+
+#[instrument]
+#[command]
+#[owners_only]
+#[description = "end the anti-raid lockdown, or every lockdown when used in a DM"]
+#[usage = "`!endlockdown`"]
+async fn endlockdown(ctx: &Context, msg: &Message) -> CommandResult {
+    let summary = end_lockdown(ctx, msg.guild_id, &msg.author).await;
+
+    announce(ctx, msg, summary, &CommandResponse::DmOwner).await
+}
+
+#[instrument]
+#[command]
+#[owners_only]
+#[description = "bind an emoji on a message to a role, or remove the binding"]
+#[usage = "`!reactionrole add <message link> <emoji> <role>` or `!reactionrole remove <message link> <emoji>`"]
+#[example = "`!reactionrole add https://discord.com/channels/1/2/3 🔔 Stream Pings`"]
+async fn reactionrole(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let action = match args.single::<String>().as_deref() {
+        Ok("add") => CommandReloadAction::Append,
+        Ok("remove") => CommandReloadAction::Remove,
+        _ => {
+            return announce(
+                ctx,
+                msg,
+                HotReloadError::ImproperFormat.to_string(),
+                &CommandResponse::DmOwner,
+            )
+            .await
+        }
+    };
+    let adding = matches!(action, CommandReloadAction::Append);
+    let result = match try_hot_reload(args, AllowedReloads::ReactionRoles(action)).await {
+        Ok(mut success_msg) => {
+            if adding {
+                for problem in check_reaction_roles(ctx).await {
+                    success_msg += &format!("\n⚠️ {}", problem);
+                }
+            }
+            success_msg
+        }
+        Err(e) => {
+            let description = e.to_string();
+            info!("{}", &description);
+            description
+        }
+    };
+
+    announce(ctx, msg, result, &CommandResponse::DmOwner).await
+}
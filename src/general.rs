@@ -0,0 +1,9 @@
+mod commands;
+mod schedule;
+use commands::*;
+
+use serenity::framework::standard::macros::group;
+
+#[group]
+#[commands(next, remindme, reminders, rmreminder, schedule)]
+struct General;
@@ -0,0 +1,182 @@
+use super::schedule::{next_stream, streams_between, Stream};
+use crate::prelude::*;
+use crate::utils::{
+    discord::unknown_command,
+    reminders::{add_reminder, cancel_reminder, parse_when, user_reminders},
+};
+use chrono::{Duration, Utc};
+use serenity::utils::{content_safe, ContentSafeOptions};
+
+#[instrument]
+#[command]
+#[description = "get reminded about something later"]
+#[usage = "`!remindme <duration or time> <what to remember>`"]
+#[example = "`!remindme 1h30m the stream starts` or `!remindme 18:00 stream time!`"]
+async fn remindme(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let words: Vec<&str> = args.rest().split_whitespace().collect();
+    let timezone = crate::CONFIG.lock().await.get_timezone();
+
+    let result = match parse_when(&words, timezone, Utc::now()) {
+        Some((due, used)) if words.len() > used => {
+            // mentions are defused so reminders can't be used to ping everyone later
+            let text = content_safe(
+                &ctx.cache,
+                words[used..].join(" "),
+                &ContentSafeOptions::default(),
+            )
+            .await;
+            match add_reminder(msg.author.id, msg.channel_id, due, text).await {
+                Ok(id) => format!(
+                    "Got it! I'll remind you <t:{}:R> (reminder #{}).",
+                    due.timestamp(),
+                    id
+                ),
+                Err(reason) => reason,
+            }
+        }
+        _ => String::from(
+            "Usage: `!remindme <duration or time> <what to remember>`\n\
+            e.g. `!remindme 10m check the oven`, `!remindme 18:00 stream time!` \
+            or `!remindme 2021-01-31 18:00 birthday stream`, up to 366 days ahead",
+        ),
+    };
+
+    announce(ctx, msg, result, &CommandResponse::Reply).await
+}
+
+#[instrument]
+#[command]
+#[description = "list your pending reminders"]
+#[usage = "`!reminders`"]
+async fn reminders(ctx: &Context, msg: &Message) -> CommandResult {
+    let reminders = user_reminders(msg.author.id).await;
+    let result = if reminders.is_empty() {
+        String::from("You have no reminders.")
+    } else {
+        let mut list = String::from("**Your reminders**\n");
+        for reminder in reminders {
+            list += &format!(
+                "> `#{}` <t:{}:f> {}\n",
+                reminder.get_id(),
+                reminder.get_due(),
+                reminder.get_text()
+            );
+        }
+        list
+    };
+
+    announce(ctx, msg, result, &CommandResponse::Dm).await
+}
+
+#[instrument]
+#[command]
+#[description = "cancel one of your reminders"]
+#[usage = "`!rmreminder <reminder number>`"]
+#[example = "`!rmreminder 12`"]
+async fn rmreminder(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let result = match args.single::<String>() {
+        Ok(id) => match id.trim_start_matches('#').parse::<u64>() {
+            Ok(id) if cancel_reminder(msg.author.id, id).await => {
+                format!("Cancelled reminder #{}.", id)
+            }
+            Ok(id) => format!("You have no reminder #{}. See `!reminders`.", id),
+            Err(_) => String::from("Usage: `!rmreminder <reminder number>`"),
+        },
+        Err(_) => String::from("Usage: `!rmreminder <reminder number>`"),
+    };
+
+    announce(ctx, msg, result, &CommandResponse::Reply).await
+}
+
+/// `<t:..>` markup shows the time in each viewer's own timezone
+fn stream_line(stream: &Stream) -> String {
+    let start = stream.get_start().timestamp();
+    let title = stream.get_title().unwrap_or("Stream");
+    if stream.is_cancelled() {
+        format!("~~<t:{}:F> {}~~ *cancelled*", start, title)
+    } else {
+        format!("<t:{}:F> **{}** (<t:{}:R>)", start, title, start)
+    }
+}
+
+#[instrument]
+#[command]
+#[description = "show this week's streams"]
+#[usage = "`!schedule`"]
+async fn schedule(ctx: &Context, msg: &Message) -> CommandResult {
+    let (stream_schedule, color, auto_delete) = {
+        let config = crate::CONFIG.lock().await;
+        (
+            config.get_stream_schedule().cloned(),
+            config.get_help_color().clone(),
+            config.get_auto_delete().clone(),
+        )
+    };
+    // a config command of the same name keeps working without a [stream_schedule]
+    let stream_schedule = match stream_schedule {
+        Some(stream_schedule) => stream_schedule,
+        None => {
+            unknown_command(ctx, msg, "schedule").await;
+            return Ok(());
+        }
+    };
+
+    let now = Utc::now();
+    let streams = streams_between(&stream_schedule, now, now + Duration::weeks(1));
+    let description = if streams.is_empty() {
+        String::from("No streams planned this week.")
+    } else {
+        streams
+            .iter()
+            .map(stream_line)
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let sent = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.colour(color);
+                e.title("Stream schedule");
+                e.description(description);
+                e.footer(|f| f.text("times are shown in your timezone"));
+                e
+            })
+        })
+        .await?;
+    clean_up(ctx, msg, vec![sent], &auto_delete).await;
+    Ok(())
+}
+
+#[instrument]
+#[command]
+#[description = "countdown to the next stream"]
+#[usage = "`!next`"]
+async fn next(ctx: &Context, msg: &Message) -> CommandResult {
+    let stream_schedule = crate::CONFIG.lock().await.get_stream_schedule().cloned();
+    let stream_schedule = match stream_schedule {
+        Some(stream_schedule) => stream_schedule,
+        None => {
+            unknown_command(ctx, msg, "next").await;
+            return Ok(());
+        }
+    };
+
+    let now = Utc::now();
+    let result = match next_stream(&stream_schedule, now) {
+        Some(stream) if stream.get_start() <= now => format!(
+            "**{}** is live now!",
+            stream.get_title().unwrap_or("The stream")
+        ),
+        Some(stream) => format!(
+            "Next up: **{}** <t:{}:R>, at <t:{}:F> your time",
+            stream.get_title().unwrap_or("Stream"),
+            stream.get_start().timestamp(),
+            stream.get_start().timestamp()
+        ),
+        None => String::from("No streams are planned right now."),
+    };
+
+    announce(ctx, msg, result, &CommandResponse::Channel).await
+}
@@ -0,0 +1,258 @@
+use crate::utils::config::{StreamScheduleData, StreamTime};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
+
+/// A scheduled stream, or a weekly one that was cancelled
+#[derive(Clone, Debug)]
+pub struct Stream {
+    start: DateTime<Utc>,
+    end: Option<DateTime<Utc>>,
+    title: Option<String>,
+    cancelled: bool,
+}
+
+impl Stream {
+    pub fn get_start(&self) -> DateTime<Utc> {
+        self.start
+    }
+
+    pub fn get_title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+}
+
+/// Streams starting between `from` and `until`, including cancelled weekly streams, in order.
+pub fn streams_between(
+    schedule: &StreamScheduleData,
+    from: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Vec<Stream> {
+    let timezone = schedule.get_timezone();
+    let mut streams = Vec::new();
+    // a day early, since a stream from yesterday can still be running
+    let mut date = from.with_timezone(&timezone).naive_local().date() - Duration::days(1);
+    let last = until.with_timezone(&timezone).naive_local().date();
+
+    while date <= last {
+        let overrides: Vec<&Option<StreamTime>> = schedule
+            .get_overrides()
+            .iter()
+            .filter(|(day, _)| *day == date)
+            .map(|(_, time)| time)
+            .collect();
+        let weekly = schedule
+            .get_weekly()
+            .iter()
+            .filter(|(day, _)| *day == date.weekday())
+            .map(|(_, time)| time);
+
+        if overrides.is_empty() {
+            streams.extend(weekly.map(|time| stream_at(timezone, date, time, false)));
+        } else {
+            streams.extend(weekly.map(|time| stream_at(timezone, date, time, true)));
+            streams.extend(
+                overrides
+                    .into_iter()
+                    .flatten()
+                    .map(|time| stream_at(timezone, date, time, false)),
+            );
+        }
+        date = match date.succ_opt() {
+            Some(next) => next,
+            None => break,
+        };
+    }
+
+    streams.retain(|s| s.start <= until && s.end.unwrap_or(s.start) >= from);
+    streams.sort_by_key(|s| s.start);
+    streams
+}
+
+/// The stream that is live at `now`, or else the next one to start.
+pub fn next_stream(schedule: &StreamScheduleData, now: DateTime<Utc>) -> Option<Stream> {
+    streams_between(schedule, now, now + Duration::weeks(5))
+        .into_iter()
+        .find(|s| !s.cancelled)
+}
+
+fn stream_at(timezone: Tz, date: NaiveDate, time: &StreamTime, cancelled: bool) -> Stream {
+    let start = local_to_utc(timezone, date.and_time(time.get_start()));
+    Stream {
+        start,
+        end: time.get_duration().map(|duration| start + duration),
+        title: time.get_title().map(String::from),
+        cancelled,
+    }
+}
+
+/// Reads a local time in `timezone`. Times skipped when the clocks go forward
+/// are read with the offset from before the jump, which moves them forward by
+/// the jump, and times repeated when the clocks go back are the earlier one.
+fn local_to_utc(timezone: Tz, local: NaiveDateTime) -> DateTime<Utc> {
+    match timezone.from_local_datetime(&local).earliest() {
+        Some(time) => time.with_timezone(&Utc),
+        None => {
+            let before = timezone
+                .offset_from_utc_datetime(&(local - Duration::days(1)))
+                .fix();
+            let utc = local - Duration::seconds(before.local_minus_utc() as i64);
+            DateTime::from_naive_utc_and_offset(utc, Utc)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::config::test_config;
+
+    const TIMEZONE: Tz = chrono_tz::America::Chicago;
+
+    async fn schedule(tables: &str) -> StreamScheduleData {
+        test_config(&format!(
+            "[stream_schedule]\ntimezone = \"America/Chicago\"\n{}",
+            tables
+        ))
+        .data()
+        .await
+        .unwrap()
+        .get_stream_schedule()
+        .unwrap()
+        .clone()
+    }
+
+    /// a local time in Chicago, like `2021-01-08 18:00`
+    fn at(local: &str) -> DateTime<Utc> {
+        let local = NaiveDateTime::parse_from_str(local, "%Y-%m-%d %H:%M").unwrap();
+        TIMEZONE
+            .from_local_datetime(&local)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn starts(streams: &[Stream]) -> Vec<(DateTime<Utc>, bool)> {
+        streams.iter().map(|s| (s.start, s.cancelled)).collect()
+    }
+
+    const FRIDAYS: &str = r#"
+        [[stream_schedule.slot]]
+        day = "Fri"
+        start = "18:00"
+        duration_mins = 180
+        title = "Spooky Friday"
+        "#;
+
+    #[tokio::test]
+    async fn weekly_slots_repeat_in_the_schedule_timezone() {
+        let schedule = schedule(FRIDAYS).await;
+        let streams = streams_between(&schedule, at("2021-01-04 00:00"), at("2021-01-17 00:00"));
+        assert_eq!(
+            starts(&streams),
+            vec![
+                (at("2021-01-08 18:00"), false),
+                (at("2021-01-15 18:00"), false)
+            ]
+        );
+        assert_eq!(streams[0].get_title(), Some("Spooky Friday"));
+    }
+
+    #[tokio::test]
+    async fn override_replaces_the_days_streams() {
+        let schedule = schedule(&format!(
+            "{}\n[[stream_schedule.override]]\ndate = \"2021-01-08\"\nstart = \"20:00\"\ntitle = \"Special\"",
+            FRIDAYS
+        ))
+        .await;
+        let streams = streams_between(&schedule, at("2021-01-04 00:00"), at("2021-01-10 00:00"));
+        assert_eq!(
+            starts(&streams),
+            vec![
+                (at("2021-01-08 18:00"), true),
+                (at("2021-01-08 20:00"), false)
+            ]
+        );
+        assert_eq!(streams[1].get_title(), Some("Special"));
+        // the override has no duration of its own
+        assert_eq!(streams[1].end, None);
+    }
+
+    #[tokio::test]
+    async fn cancelled_day_is_skipped_by_next() {
+        let schedule = schedule(&format!(
+            "{}\n[[stream_schedule.override]]\ndate = \"2021-01-08\"",
+            FRIDAYS
+        ))
+        .await;
+        let now = at("2021-01-04 00:00");
+        assert_eq!(
+            starts(&streams_between(&schedule, now, at("2021-01-10 00:00"))),
+            vec![(at("2021-01-08 18:00"), true)]
+        );
+        assert_eq!(
+            next_stream(&schedule, now).map(|s| s.start),
+            Some(at("2021-01-15 18:00"))
+        );
+    }
+
+    #[tokio::test]
+    async fn stream_from_yesterday_is_still_live() {
+        let schedule = schedule(
+            r#"
+            [[stream_schedule.slot]]
+            day = "Fri"
+            start = "23:00"
+            duration_mins = 180
+            "#,
+        )
+        .await;
+        let live = next_stream(&schedule, at("2021-01-09 01:00")).unwrap();
+        assert_eq!(live.start, at("2021-01-08 23:00"));
+
+        // once it's over the next one is a week later
+        let next = next_stream(&schedule, at("2021-01-09 02:30")).unwrap();
+        assert_eq!(next.start, at("2021-01-15 23:00"));
+    }
+
+    #[tokio::test]
+    async fn slot_in_a_dst_gap_moves_forward() {
+        // the clocks in Chicago jumped from 02:00 to 03:00 on 2021-03-14
+        let schedule = schedule(
+            r#"
+            [[stream_schedule.slot]]
+            day = "Sun"
+            start = "02:30"
+            "#,
+        )
+        .await;
+        let streams = streams_between(&schedule, at("2021-03-08 00:00"), at("2021-03-22 00:00"));
+        assert_eq!(
+            starts(&streams),
+            vec![
+                (at("2021-03-14 03:30"), false),
+                (at("2021-03-21 02:30"), false)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn slot_in_a_repeated_hour_is_the_first_one() {
+        // the clocks in Chicago went back from 02:00 to 01:00 on 2021-11-07
+        let schedule = schedule(
+            r#"
+            [[stream_schedule.slot]]
+            day = "Sun"
+            start = "01:30"
+            "#,
+        )
+        .await;
+        let streams = streams_between(&schedule, at("2021-11-06 00:00"), at("2021-11-08 00:00"));
+        assert_eq!(
+            streams[0].start,
+            "2021-11-07T06:30:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+    }
+}
@@ -1,6 +1,8 @@
 /// SEE https://docs.rs/serenity/0.9.2/serenity/ for documentation on Discord API for Rust
 /// SEE https://github.com/serenity-rs/serenity/tree/current/examples for examples
 mod admin;
+mod general;
+mod moderators;
 mod mods;
 mod utils;
 
@@ -15,8 +17,15 @@ use once_cell::sync::Lazy;
 static OWNER: Lazy<Mutex<User>> = Lazy::new(|| Mutex::default());
 
 use utils::config::{get_conf, ConfigData};
+/// Replaced on every hot reload. Copy what's needed out of it rather than
+/// holding the lock across Discord or HTTP calls, which would stall every
+/// other handler.
 static CONFIG: Lazy<Mutex<ConfigData>> = Lazy::new(|| Mutex::default());
 
+use once_cell::sync::OnceCell;
+use serenity::cache::Cache;
+static CACHE: OnceCell<Arc<Cache>> = OnceCell::new();
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     // try getting $MUFFETBOT_CONFIG env or trigger initial setup if not present
@@ -44,7 +53,19 @@ async fn main() -> anyhow::Result<()> {
 
     // getting config data from file at $MUFFETBOT_CONFIG path
     let config = get_conf(&config_path).await?;
+
+    // `muffet-bot --check-links` prints a link report instead of starting the bot
+    if env::args().any(|arg| arg == "--check-links") {
+        let reports = utils::links::check_links(config.data().await?.get_commands()).await;
+        println!("{}", utils::links::format_reports(&reports));
+        if reports.iter().any(|report| report.is_broken()) {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     let prefix = config.get_command_prefix();
+    let help_names = config.get_help_names();
     let token = config.get_token();
 
     // creating log file subscriber
@@ -55,7 +76,7 @@ async fn main() -> anyhow::Result<()> {
     tracing_subscriber::fmt().with_writer(non_blocking).init();
 
     // setting initial global config object
-    let config_data = config.data().await;
+    let config_data = config.data().await?;
     *CONFIG.lock().await = config_data;
 
     info!("Hydrated global config");
@@ -85,15 +106,37 @@ async fn main() -> anyhow::Result<()> {
                 .owners(owners)
         })
         .unrecognised_command(unknown_command)
-        .help(&MUFFET_HELP)
+        .help(configured_help(help_names))
         .group(&admin::ADMIN_GROUP)
+        .group(&general::GENERAL_GROUP)
+        .group(&moderators::MODERATORS_GROUP)
         .group(&mods::commands::CUSTOMCOMMANDS_GROUP);
 
     // setting up client to subscribe to Discord events
     let mut client = serenity::client::Client::builder(&token)
+        .event_handler(utils::events::Handler)
         .framework(framework)
         .await
         .expect("Err creating client");
+    let _ = CACHE.set(Arc::clone(&client.cache_and_http.cache));
+    tokio::spawn(utils::scheduler::run_schedules(Arc::clone(
+        &client.cache_and_http.http,
+    )));
+    tokio::spawn(utils::feeds::run_feeds(Arc::clone(
+        &client.cache_and_http.http,
+    )));
+    tokio::spawn(utils::reminders::run_reminders(Arc::clone(
+        &client.cache_and_http.http,
+    )));
+    tokio::spawn(utils::timers::run_timers(Arc::clone(
+        &client.cache_and_http.http,
+    )));
+    tokio::spawn(utils::links::run_link_checks(Arc::clone(
+        &client.cache_and_http.http,
+    )));
+    tokio::spawn(utils::moderation::run_timeouts(Arc::clone(
+        &client.cache_and_http.http,
+    )));
     {
         let mut data = client.data.write().await;
         data.insert::<ShardManagerContainer>(Arc::clone(&client.shard_manager));
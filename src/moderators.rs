@@ -0,0 +1,32 @@
+mod commands;
+use commands::*;
+
+use crate::prelude::*;
+use crate::utils::moderation::is_moderator;
+use serenity::framework::standard::{
+    macros::{check, group},
+    CheckResult, CommandOptions,
+};
+
+// passes for the owner and members with one of the `moderator_roles`
+#[check]
+#[name = "Moderator"]
+async fn moderator_check(
+    ctx: &Context,
+    msg: &Message,
+    _: &mut Args,
+    _: &CommandOptions,
+) -> CheckResult {
+    let moderator_roles = crate::CONFIG.lock().await.get_moderator_roles().to_vec();
+    if is_moderator(ctx, msg, &moderator_roles).await {
+        CheckResult::Success
+    } else {
+        CheckResult::new_log(format!("{} isn't a moderator", msg.author.tag()))
+    }
+}
+
+#[group]
+#[only_in(guilds)]
+#[checks(Moderator)]
+#[commands(clearwarn, note, warn, warnings)]
+struct Moderators;
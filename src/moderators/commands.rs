@@ -0,0 +1,266 @@
+use crate::prelude::*;
+use crate::utils::warnings::{
+    add_record, clear_warnings, enforce_threshold, user_records, RecordKind,
+};
+use serenity::model::id::{ChannelId, GuildId, UserId};
+
+/// Why `user_id` can't be warned, if they can't: the owner, bots and
+/// moderators are out of reach of the warning thresholds
+async fn protected(
+    ctx: &Context,
+    guild_id: GuildId,
+    user_id: UserId,
+    moderator_roles: &[String],
+) -> Option<&'static str> {
+    if user_id == crate::OWNER.lock().await.id {
+        return Some("the bot owner can't be warned");
+    }
+    let is_bot = match user_id.to_user(ctx).await {
+        Ok(user) => user.bot,
+        Err(e) => {
+            info!("Unable to look up {}: {}", user_id, e);
+            user_id == ctx.cache.current_user_id().await
+        }
+    };
+    if is_bot {
+        return Some("bots can't be warned");
+    }
+    let roles = member_roles(ctx, guild_id, user_id).await;
+    if moderator_roles.iter().any(|moderator| {
+        roles
+            .iter()
+            .any(|role| role.eq_ignore_ascii_case(moderator))
+    }) {
+        return Some("moderators can't be warned");
+    }
+    None
+}
+
+fn plural(count: usize, word: &str) -> String {
+    if count == 1 {
+        format!("{} {}", count, word)
+    } else {
+        format!("{} {}s", count, word)
+    }
+}
+
+#[instrument]
+#[command]
+#[description = "warn a member, who is told by DM"]
+#[usage = "`!warn @member <reason>`"]
+#[example = "`!warn @spammer posting scam links`"]
+async fn warn(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = match msg.guild_id {
+        Some(guild_id) => guild_id,
+        None => return Ok(()),
+    };
+    let (user_id, reason) = match (args.single::<UserId>(), args.remains()) {
+        (Ok(user_id), Some(reason)) => (user_id, reason.trim().to_string()),
+        _ => {
+            return announce(
+                ctx,
+                msg,
+                "Usage: `!warn @member <reason>`",
+                &CommandResponse::Reply,
+            )
+            .await
+        }
+    };
+    let (warnings, moderator_roles) = {
+        let config = crate::CONFIG.lock().await;
+        (
+            config.get_warnings().clone(),
+            config.get_moderator_roles().to_vec(),
+        )
+    };
+    if let Some(reason) = protected(ctx, guild_id, user_id, &moderator_roles).await {
+        return announce(
+            ctx,
+            msg,
+            format!("I won't warn <@{}>, {}.", user_id, reason),
+            &CommandResponse::Reply,
+        )
+        .await;
+    }
+
+    let id = add_record(
+        RecordKind::Warning,
+        guild_id,
+        user_id,
+        msg.author.id,
+        reason.clone(),
+    )
+    .await;
+    let count = user_records(guild_id, user_id)
+        .await
+        .iter()
+        .filter(|r| r.get_kind() == RecordKind::Warning && !r.is_expired(&warnings))
+        .count();
+
+    // sent before any kick or ban, since the bot can't DM people it shares no server with
+    if warnings.dms_member() {
+        let server = guild_id.name(&ctx.cache).await.unwrap_or_default();
+        let dm = format!(
+            "⚠️ You've been warned in **{}**: {}\n*that's {}*",
+            server,
+            reason,
+            plural(count, "warning")
+        );
+        let sent = match user_id.create_dm_channel(&ctx.http).await {
+            Ok(channel) => channel.say(&ctx.http, dm).await.map(|_| ()),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = sent {
+            info!("Unable to DM warning {}: {}", id, e);
+        }
+    }
+
+    let mut result = format!(
+        "<@{}> has been warned, that's {}",
+        user_id,
+        plural(count, "warning")
+    );
+    if let Some(enforced) = enforce_threshold(ctx, guild_id, user_id, &warnings, count).await {
+        result += &format!(" and {}", enforced);
+    }
+    result += ".";
+
+    if let Some(log_channel) = warnings.get_log_channel() {
+        let report = format!(
+            "⚠️ {} warned <@{}> (warning #{}): {}\n{}",
+            msg.author.tag(),
+            user_id,
+            id,
+            reason,
+            result
+        );
+        if let Err(e) = say_split(&ctx.http, ChannelId(log_channel), &report, &[]).await {
+            info!("Unable to log warning {}: {}", id, e);
+        }
+    }
+
+    announce(ctx, msg, result, &CommandResponse::Channel).await
+}
+
+#[instrument]
+#[command]
+#[description = "list a member's warnings and notes, sent to you by DM"]
+#[usage = "`!warnings @member`"]
+async fn warnings(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = match msg.guild_id {
+        Some(guild_id) => guild_id,
+        None => return Ok(()),
+    };
+    let user_id = match args.single::<UserId>() {
+        Ok(user_id) => user_id,
+        Err(_) => {
+            return announce(
+                ctx,
+                msg,
+                "Usage: `!warnings @member`",
+                &CommandResponse::Reply,
+            )
+            .await
+        }
+    };
+    let warnings = crate::CONFIG.lock().await.get_warnings().clone();
+
+    let records = user_records(guild_id, user_id).await;
+    let result = if records.is_empty() {
+        format!("<@{}> has no warnings or notes.", user_id)
+    } else {
+        let mut list = format!("**Warnings and notes for <@{}>**\n", user_id);
+        for record in records {
+            let (icon, expired) = match record.get_kind() {
+                RecordKind::Warning if record.is_expired(&warnings) => ("⚠️", " *(expired)*"),
+                RecordKind::Warning => ("⚠️", ""),
+                RecordKind::Note => ("📝", ""),
+            };
+            list += &format!(
+                "> `#{}` {} <t:{}:d> by <@{}>: {}{}\n",
+                record.get_id(),
+                icon,
+                record.get_timestamp(),
+                record.get_moderator_id(),
+                record.get_reason(),
+                expired
+            );
+        }
+        list
+    };
+
+    announce(ctx, msg, result, &CommandResponse::Dm).await
+}
+
+#[instrument]
+#[command]
+#[description = "remove one of a member's warnings, or all of them"]
+#[usage = "`!clearwarn @member <warning id>` or `!clearwarn @member` for all"]
+#[example = "`!clearwarn @spidey 12`"]
+async fn clearwarn(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = match msg.guild_id {
+        Some(guild_id) => guild_id,
+        None => return Ok(()),
+    };
+    let user_id = match args.single::<UserId>() {
+        Ok(user_id) => user_id,
+        Err(_) => {
+            return announce(
+                ctx,
+                msg,
+                "Usage: `!clearwarn @member <warning id>` or `!clearwarn @member`",
+                &CommandResponse::Reply,
+            )
+            .await
+        }
+    };
+    let id = args
+        .single::<String>()
+        .ok()
+        .and_then(|id| id.trim_start_matches('#').parse::<u64>().ok());
+
+    let result = match (clear_warnings(guild_id, user_id, id).await, id) {
+        (0, Some(id)) => format!("<@{}> has no warning #{}.", user_id, id),
+        (0, None) => format!("<@{}> has no warnings.", user_id),
+        (_, Some(id)) => format!("Removed warning #{} from <@{}>.", id, user_id),
+        (removed, None) => format!(
+            "Removed {} from <@{}>.",
+            plural(removed, "warning"),
+            user_id
+        ),
+    };
+
+    announce(ctx, msg, result, &CommandResponse::Reply).await
+}
+
+#[instrument]
+#[command]
+#[description = "leave a note about a member that only moderators can see"]
+#[usage = "`!note @member <note>`"]
+#[example = "`!note @spidey asked about the giveaway rules twice`"]
+async fn note(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = match msg.guild_id {
+        Some(guild_id) => guild_id,
+        None => return Ok(()),
+    };
+    // notes shouldn't stay up in the channel they were written in
+    if let Err(e) = msg.delete(ctx).await {
+        info!("Unable to delete note message: {}", e);
+    }
+    let result = match (args.single::<UserId>(), args.remains()) {
+        (Ok(user_id), Some(note)) => {
+            let id = add_record(
+                RecordKind::Note,
+                guild_id,
+                user_id,
+                msg.author.id,
+                note.trim().to_string(),
+            )
+            .await;
+            format!("Saved note #{} about <@{}>.", id, user_id)
+        }
+        _ => String::from("Usage: `!note @member <note>`"),
+    };
+
+    announce(ctx, msg, result, &CommandResponse::Dm).await
+}
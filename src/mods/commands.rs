@@ -11,7 +11,11 @@ what the config offers are able to be compiled
 as part of the mods folder.
 
 This grants access to all of the Discord API,
-Serenity API, and muffetbot's built-in webscraper.
+Serenity API, and muffetbot's built-in webscraper
+in `utils::web` (`fetch` and `scrape`).
+
+Simple scraping needs no code, config commands
+accept `scrape = { url, selector, attr }`.
 "#;
 
 #[instrument]
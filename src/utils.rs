@@ -1,3 +1,21 @@
+pub mod anti_raid;
 pub mod config;
 pub mod discord;
+pub mod events;
+pub mod feeds;
+#[cfg(test)]
+pub mod fixture;
+pub mod link_filter;
+pub mod links;
+pub mod moderation;
 pub mod prelude;
+pub mod reaction_roles;
+pub mod reminders;
+pub mod scheduler;
+pub mod split;
+pub mod store;
+pub mod timers;
+pub mod warnings;
+pub mod web;
+pub mod webhooks;
+pub mod word_filter;
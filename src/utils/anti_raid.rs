@@ -0,0 +1,244 @@
+use crate::prelude::*;
+use crate::utils::config::AntiRaidData;
+use crate::utils::store::Store;
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use serde_derive::{Deserialize, Serialize};
+use serenity::model::{
+    guild::{Member, VerificationLevel},
+    id::{ChannelId, GuildId, UserId},
+    user::User,
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Instant,
+};
+
+/// data file holding the guilds in lockdown, so a restart doesn't end it
+const LOCKDOWN_FILE: &str = "lockdown.toml";
+
+/// A guild in lockdown, with the verification level it had before
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct Lockdown {
+    guild_id: u64,
+    previous_level: VerificationLevel,
+    /// unix timestamp
+    started: i64,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct LockdownList {
+    lockdowns: Vec<Lockdown>,
+}
+
+static LOCKDOWNS: Store<LockdownList> = Store::new(LOCKDOWN_FILE);
+
+#[derive(Debug)]
+struct Join {
+    at: Instant,
+    user_id: u64,
+    suspicious: bool,
+}
+
+/// recent joins of each guild, within the join window
+static JOINS: Lazy<Mutex<HashMap<u64, VecDeque<Join>>>> = Lazy::new(Mutex::default);
+
+pub async fn is_locked_down(guild_id: GuildId) -> bool {
+    LOCKDOWNS
+        .lock()
+        .await
+        .lockdowns
+        .iter()
+        .any(|lockdown| lockdown.guild_id == guild_id.0)
+}
+
+/// Tracks a member joining, and locks the guild down once the joins within
+/// the window look like a raid.
+#[instrument]
+pub async fn watch_join(ctx: &Context, guild_id: GuildId, member: &Member) {
+    let anti_raid = match crate::CONFIG.lock().await.get_anti_raid() {
+        Some(anti_raid) => anti_raid.clone(),
+        None => return,
+    };
+
+    let (joined, suspicious, recent) = {
+        let mut all_joins = JOINS.lock().await;
+        let joins = all_joins.entry(guild_id.0).or_default();
+        while joins
+            .front()
+            .is_some_and(|join| join.at.elapsed() >= anti_raid.get_join_window())
+        {
+            joins.pop_front();
+        }
+        joins.push_back(Join {
+            at: Instant::now(),
+            user_id: member.user.id.0,
+            suspicious: anti_raid.is_suspicious(&member.user),
+        });
+        (
+            joins.len(),
+            joins.iter().filter(|join| join.suspicious).count(),
+            joins.iter().map(|join| join.user_id).collect::<Vec<u64>>(),
+        )
+    };
+    if joined < anti_raid.get_join_threshold() && suspicious < anti_raid.get_suspicious_threshold()
+    {
+        return;
+    }
+
+    let reason = format!(
+        "{} members joined within {} seconds, {} of them with new accounts or default avatars",
+        joined,
+        anti_raid.get_join_window().as_secs(),
+        suspicious
+    );
+    lock_down(ctx, guild_id, &anti_raid, &reason, &recent).await;
+}
+
+async fn current_level(ctx: &Context, guild_id: GuildId) -> Option<VerificationLevel> {
+    match ctx
+        .cache
+        .guild_field(guild_id, |g| g.verification_level)
+        .await
+    {
+        Some(level) => Some(level),
+        None => ctx
+            .http
+            .get_guild(guild_id.0)
+            .await
+            .ok()
+            .map(|g| g.verification_level),
+    }
+}
+
+/// raises the verification level, which also pauses welcome messages, and alerts the admins
+async fn lock_down(
+    ctx: &Context,
+    mut guild_id: GuildId,
+    anti_raid: &AntiRaidData,
+    reason: &str,
+    recent: &[u64],
+) {
+    let previous_level = match current_level(ctx, guild_id).await {
+        Some(level) => level,
+        None => {
+            info!("Unable to look up the verification level of {}", guild_id);
+            return;
+        }
+    };
+    {
+        let mut list = LOCKDOWNS.lock().await;
+        if list.lockdowns.iter().any(|l| l.guild_id == guild_id.0) {
+            return;
+        }
+        list.lockdowns.push(Lockdown {
+            guild_id: guild_id.0,
+            previous_level,
+            started: Utc::now().timestamp(),
+        });
+        LOCKDOWNS.persist(&list);
+    }
+    info!("Locking down {}: {}", guild_id, reason);
+
+    // never lowered, in case it was already stricter
+    let level = previous_level.max(anti_raid.get_verification_level());
+    let raised = match guild_id
+        .edit(&ctx.http, |g| g.verification_level(level))
+        .await
+    {
+        Ok(_) => format!("The verification level is raised to {:?}", level),
+        Err(e) => {
+            info!("Unable to raise the verification level: {}", e);
+            format!("I couldn't raise the verification level ({})", e)
+        }
+    };
+    let prefix = crate::CONFIG.lock().await.get_command_prefix().to_string();
+    let joins: Vec<String> = recent.iter().map(|id| format!("<@{}>", id)).collect();
+    let alert = format!(
+        "🚨 **Lockdown:** {}.\n{} and welcome messages are paused until an admin uses `{}endlockdown`.\nRecent joins: {}",
+        reason,
+        raised,
+        prefix,
+        joins.join(" ")
+    );
+    alert_admins(ctx, anti_raid, &alert).await;
+}
+
+/// DMs the owner and the configured admins, and posts in the audit channel
+async fn alert_admins(ctx: &Context, anti_raid: &AntiRaidData, alert: &str) {
+    let mut recipients = vec![crate::OWNER.lock().await.id.0];
+    for id in anti_raid.get_alert_users() {
+        if !recipients.contains(id) {
+            recipients.push(*id);
+        }
+    }
+    for id in recipients {
+        let sent = match UserId(id).create_dm_channel(&ctx.http).await {
+            Ok(dm) => say_split(&ctx.http, dm.id, alert, &[]).await.map(|_| ()),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = sent {
+            info!("Unable to alert {} about the lockdown: {}", id, e);
+        }
+    }
+
+    if let Some(channel) = anti_raid.get_audit_channel() {
+        if let Err(e) = say_split(&ctx.http, ChannelId(channel), alert, &[]).await {
+            info!("Unable to post lockdown alert: {}", e);
+        }
+    }
+}
+
+/// Ends the lockdown of a guild, or of every guild when there is no
+/// `guild_id`, restoring their verification levels. Returns what happened.
+pub async fn end_lockdown(ctx: &Context, guild_id: Option<GuildId>, ended_by: &User) -> String {
+    let ended: Vec<Lockdown> = {
+        let mut list = LOCKDOWNS.lock().await;
+        let (ended, ongoing) = list
+            .lockdowns
+            .drain(..)
+            .partition(|l| guild_id.is_none_or(|id| id.0 == l.guild_id));
+        list.lockdowns = ongoing;
+        if !ended.is_empty() {
+            LOCKDOWNS.persist(&list);
+        }
+        ended
+    };
+    if ended.is_empty() {
+        return String::from("There's no lockdown to end.");
+    }
+
+    let mut summary = String::new();
+    for lockdown in ended {
+        // the raid's joins would set the lockdown off again right away
+        JOINS.lock().await.remove(&lockdown.guild_id);
+
+        let mut guild = GuildId(lockdown.guild_id);
+        summary += &match guild
+            .edit(&ctx.http, |g| g.verification_level(lockdown.previous_level))
+            .await
+        {
+            Ok(_) => format!(
+                "Lockdown since <t:{}:R> ended, the verification level is back to {:?}.\n",
+                lockdown.started, lockdown.previous_level
+            ),
+            Err(e) => format!(
+                "Lockdown since <t:{}:R> ended, but I couldn't put the verification level back to {:?}: {}\n",
+                lockdown.started, lockdown.previous_level, e
+            ),
+        };
+    }
+
+    let audit_channel = crate::CONFIG
+        .lock()
+        .await
+        .get_anti_raid()
+        .and_then(|anti_raid| anti_raid.get_audit_channel());
+    if let Some(channel) = audit_channel {
+        let report = format!("✅ {} ended the lockdown.\n{}", ended_by.tag(), summary);
+        if let Err(e) = say_split(&ctx.http, ChannelId(channel), &report, &[]).await {
+            info!("Unable to post lockdown end: {}", e);
+        }
+    }
+    summary
+}
@@ -1,13 +1,23 @@
+use crate::utils::{link_filter::filter_domain, word_filter::word_list_regex};
 use anyhow::Result;
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc, Weekday};
+use chrono_tz::Tz;
+use regex::{Regex, RegexBuilder};
 use serde_derive::{Deserialize, Serialize};
+use serenity::{
+    cache::Cache,
+    model::{channel::ReactionType, guild::VerificationLevel, misc::Mentionable},
+};
+use std::str::FromStr;
+use tracing::info;
 
-#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
-#[serde(rename_all = "lowercase")]
+#[derive(Clone, Debug, PartialEq)]
 pub enum CommandResponse {
     Channel,
+    /// posts to the given channel ids instead of the invoking channel
+    Channels(Vec<u64>),
     Dm,
     Embed,
-    #[serde(rename = "dm owner")]
     DmOwner,
     Reply,
 }
@@ -18,18 +28,395 @@ impl Default for CommandResponse {
     }
 }
 
+impl std::str::FromStr for CommandResponse {
+    type Err = String;
+
+    fn from_str(response_type: &str) -> Result<Self, Self::Err> {
+        use CommandResponse::*;
+        match response_type.trim() {
+            "channel" => Ok(Channel),
+            "dm" => Ok(Dm),
+            "embed" => Ok(Embed),
+            "dm owner" => Ok(DmOwner),
+            "reply" => Ok(Reply),
+            other if other.starts_with("channel:") => {
+                let mut ids = Vec::new();
+                for id in other["channel:".len()..].split(',') {
+                    match id.trim().parse::<u64>() {
+                        Ok(id) => ids.push(id),
+                        Err(_) => return Err(format!("invalid channel id `{}`", id.trim())),
+                    }
+                }
+                Ok(Channels(ids))
+            }
+            other => Err(format!("unknown response type `{}`", other)),
+        }
+    }
+}
+
+impl std::fmt::Display for CommandResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        use CommandResponse::*;
+        match self {
+            Channel => write!(f, "channel"),
+            Channels(ids) => write!(
+                f,
+                "channel:{}",
+                ids.iter()
+                    .map(|id| id.to_string())
+                    .collect::<Vec<String>>()
+                    .join(",")
+            ),
+            Dm => write!(f, "dm"),
+            Embed => write!(f, "embed"),
+            DmOwner => write!(f, "dm owner"),
+            Reply => write!(f, "reply"),
+        }
+    }
+}
+
+impl serde::Serialize for CommandResponse {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for CommandResponse {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let response_type = <String as serde::Deserialize>::deserialize(deserializer)?;
+        response_type.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// One or several channel ids, as in `channel = 123` or `channel = [123, 456]`
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(untagged)]
+enum ChannelTargets {
+    One(u64),
+    Many(Vec<u64>),
+}
+
+impl From<ChannelTargets> for Vec<u64> {
+    fn from(targets: ChannelTargets) -> Self {
+        match targets {
+            ChannelTargets::One(id) => vec![id],
+            ChannelTargets::Many(ids) => ids,
+        }
+    }
+}
+
+/// When the bot should clean up after a command
+#[derive(Clone, Debug, Default)]
+pub struct AutoDelete {
+    after_secs: Option<u64>,
+    trigger: bool,
+}
+
+impl AutoDelete {
+    /// Seconds after which the bot's response is deleted, if ever.
+    pub fn get_after_secs(&self) -> Option<u64> {
+        self.after_secs
+    }
+
+    /// Whether the message that triggered the command is deleted.
+    pub fn deletes_trigger(&self) -> bool {
+        self.trigger
+    }
+
+    /// Same response deletion, but the triggering message is kept.
+    pub fn without_trigger(&self) -> AutoDelete {
+        AutoDelete {
+            after_secs: self.after_secs,
+            trigger: false,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 struct Command {
     admin: Option<bool>,
     name: String,
+    channel: Option<ChannelTargets>,
     color: Option<Color>,
+    delete_after_secs: Option<u64>,
+    delete_trigger: Option<bool>,
+    files: Option<Vec<String>>,
     help: Option<String>,
+    hidden: Option<bool>,
+    min_args: Option<usize>,
+    max_args: Option<usize>,
     #[serde(rename = "path")]
     url_path: Option<String>,
+    react: Option<Vec<String>>,
+    response_type: Option<CommandResponse>,
+    run: Option<Vec<String>>,
+    combine: Option<bool>,
+    target: Option<String>,
+    unfurl: Option<bool>,
+    usage: Option<String>,
+    // tables have to come after plain values to serialize
+    persona: Option<Persona>,
+    scrape: Option<Scrape>,
+    #[serde(rename = "variant")]
+    variants: Option<Vec<Variant>>,
+}
+
+/// Name and avatar the bot posts under through a channel webhook
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct Persona {
+    name: String,
+    avatar: Option<String>,
+}
+
+impl Persona {
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_avatar(&self) -> Option<&str> {
+        self.avatar.as_deref()
+    }
+}
+
+/// seconds a scraped value is reused when `ttl_secs` isn't set
+const DEFAULT_SCRAPE_TTL_SECS: u64 = 300;
+
+/// Part of a web page filled into a command's `{scraped}` placeholder
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct Scrape {
+    url: String,
+    selector: String,
+    attr: Option<String>,
+    ttl_secs: Option<u64>,
+}
+
+impl Scrape {
+    pub fn get_url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn get_selector(&self) -> &str {
+        &self.selector
+    }
+
+    /// Attribute to read, the element's text is used when there is none.
+    pub fn get_attr(&self) -> Option<&str> {
+        self.attr.as_deref()
+    }
+
+    pub fn get_ttl(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.ttl_secs.unwrap_or(DEFAULT_SCRAPE_TTL_SECS))
+    }
+}
+
+/// Alternative target for members with any of the given roles
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+struct Variant {
+    roles: Vec<String>,
+    target: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+struct Responder {
+    contains: Option<String>,
+    starts_with: Option<String>,
+    regex: Option<String>,
+    case_sensitive: Option<bool>,
+    channels: Option<Vec<u64>>,
+    cooldown_secs: Option<u64>,
+    react: Option<Vec<String>>,
     response_type: Option<CommandResponse>,
     target: Option<String>,
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+struct Schedule {
+    name: String,
+    cron: Option<String>,
+    interval_mins: Option<u64>,
+    timezone: Option<String>,
+    channel: ChannelTargets,
+    target: Option<String>,
+    command: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+struct Feed {
+    url: String,
+    interval_mins: Option<u64>,
+    channel: ChannelTargets,
+    template: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+struct Timer {
+    channel: u64,
+    interval_mins: u64,
+    min_messages: Option<u64>,
+    random: Option<bool>,
+    messages: Option<Vec<String>>,
+    commands: Option<Vec<String>>,
+}
+
+/// What a moderation filter does with a message it catches
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum FilterAction {
+    /// removes the message
+    #[default]
+    Delete,
+    /// removes the message and tells its author why
+    Warn,
+    /// only reports the message in the log channel
+    Log,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+struct LinkFilter {
+    allow: Option<Vec<String>>,
+    deny: Option<Vec<String>>,
+    block_invites: Option<bool>,
+    exempt_roles: Option<Vec<String>>,
+    exempt_channels: Option<Vec<u64>>,
+    action: Option<FilterAction>,
+    log_channel: Option<u64>,
+}
+
+/// What the word filter does with a message it catches, mildest first
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum WordAction {
+    /// removes the message
+    #[default]
+    Delete,
+    /// removes the message and tells its author why by DM
+    Warn,
+    /// also gives its author the timeout role for a while
+    Timeout,
+    /// also kicks its author from the server
+    Kick,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+struct WordRule {
+    pattern: String,
+    action: Option<WordAction>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+struct WordFilter {
+    words: Option<Vec<String>>,
+    patterns: Option<Vec<String>>,
+    action: Option<WordAction>,
+    escalation: Option<Vec<WordAction>>,
+    strike_window_mins: Option<u64>,
+    timeout_role: Option<String>,
+    timeout_mins: Option<u64>,
+    exempt_roles: Option<Vec<String>>,
+    exempt_channels: Option<Vec<u64>>,
+    log_channel: Option<u64>,
+    // tables have to come after plain values to serialize
+    #[serde(rename = "rule")]
+    rules: Option<Vec<WordRule>>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+struct Greeting {
+    channel: u64,
+    message: String,
+    embed: Option<bool>,
+    color: Option<Color>,
+    dm: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+struct StreamSlot {
+    day: String,
+    start: String,
+    duration_mins: Option<u64>,
+    title: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+struct StreamOverride {
+    date: String,
+    /// left out to cancel the day's streams
+    start: Option<String>,
+    duration_mins: Option<u64>,
+    title: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+struct AntiRaid {
+    join_threshold: Option<usize>,
+    suspicious_threshold: Option<usize>,
+    join_window_secs: Option<u64>,
+    min_account_age_hours: Option<u64>,
+    flag_default_avatar: Option<bool>,
+    verification_level: Option<String>,
+    audit_channel: Option<u64>,
+    alert_users: Option<Vec<u64>>,
+}
+
+/// What a reaction role does beyond giving the role on a reaction
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ReactionRoleMode {
+    /// takes the role away again when the reaction is removed
+    #[default]
+    Toggle,
+    /// like toggle, but also takes away the message's other roles, so only one can be picked
+    Unique,
+    /// never takes the role away, for rules and verification messages
+    Verify,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+struct ReactionRole {
+    /// link to the message, as copied in Discord
+    message: String,
+    emoji: String,
+    role: String,
+    mode: Option<ReactionRoleMode>,
+}
+
+/// What happens automatically once a member collects enough warnings
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum WarnAction {
+    /// gives the member the timeout role for a while
+    Timeout,
+    Kick,
+    Ban,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+struct WarnThreshold {
+    count: usize,
+    action: WarnAction,
+    timeout_mins: Option<u64>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+struct Warnings {
+    expire_days: Option<u64>,
+    dm: Option<bool>,
+    timeout_role: Option<String>,
+    log_channel: Option<u64>,
+    // tables have to come after plain values to serialize
+    #[serde(rename = "threshold")]
+    thresholds: Option<Vec<WarnThreshold>>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+struct StreamSchedule {
+    timezone: Option<String>,
+    #[serde(rename = "slot")]
+    slots: Option<Vec<StreamSlot>>,
+    #[serde(rename = "override")]
+    overrides: Option<Vec<StreamOverride>>,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename = "muffetbot")]
 pub struct Config {
@@ -37,20 +424,73 @@ pub struct Config {
     discord_token: String,
     log_path: String,
     help_message: Option<String>,
+    help_command: Option<String>,
+    help_aliases: Option<Vec<String>>,
+    help_response_type: Option<CommandResponse>,
+    help_title: Option<String>,
+    help_footer: Option<String>,
+    help_thumbnail: Option<String>,
+    help_author: Option<String>,
     command_prefix: Option<String>,
+    delete_after_secs: Option<u64>,
+    delete_trigger: Option<bool>,
     site_url: Option<String>,
+    timezone: Option<String>,
+    reminder_response_type: Option<CommandResponse>,
+    link_check_hours: Option<u64>,
+    moderator_roles: Option<Vec<String>>,
+    // tables have to come after plain values to serialize
+    help_persona: Option<Persona>,
     #[serde(rename = "command")]
     commands: Option<Vec<Command>>,
+    #[serde(rename = "responder")]
+    responders: Option<Vec<Responder>>,
+    #[serde(rename = "schedule")]
+    schedules: Option<Vec<Schedule>>,
+    #[serde(rename = "feed")]
+    feeds: Option<Vec<Feed>>,
+    #[serde(rename = "timer")]
+    timers: Option<Vec<Timer>>,
+    welcome: Option<Greeting>,
+    goodbye: Option<Greeting>,
+    stream_schedule: Option<StreamSchedule>,
+    link_filter: Option<LinkFilter>,
+    word_filter: Option<WordFilter>,
+    anti_raid: Option<AntiRaid>,
+    warnings: Option<Warnings>,
+    #[serde(rename = "reaction_role")]
+    reaction_roles: Option<Vec<ReactionRole>>,
+    /// directory of the config file, which relative paths are resolved against
+    #[serde(skip)]
+    config_dir: Option<PathBuf>,
 }
 
+/// Largest attachment Discord accepts without server boosts
+const MAX_FILE_SIZE: u64 = 8 * 1024 * 1024;
+
 #[derive(Clone, Debug)]
 pub struct CommandData {
     admin: bool,
+    auto_delete: AutoDelete,
     color: Color,
+    files: Vec<PathBuf>,
     help: String,
+    hidden: bool,
+    min_args: usize,
+    max_args: Option<usize>,
+    persona: Option<Persona>,
+    positionals: usize,
+    react: Vec<String>,
+    reactions: Vec<ReactionType>,
     response_type: CommandResponse,
+    run: Vec<String>,
+    combine: bool,
+    scrape: Option<Scrape>,
     trigger: String,
+    unfurl: bool,
+    usage: String,
     value: String,
+    variants: Vec<(Vec<String>, String)>,
 }
 
 impl Default for CommandData {
@@ -66,7 +506,9 @@ impl Into<CommandData> for &serenity::framework::standard::Command {
     fn into(self) -> CommandData {
         CommandData {
             admin: self.options.owners_only,
+            auto_delete: AutoDelete::default(),
             color: Color::default(),
+            files: Vec::new(),
             help: {
                 let help = if self.options.owners_only {
                     String::from("**admin command**\n")
@@ -86,9 +528,22 @@ impl Into<CommandData> for &serenity::framework::standard::Command {
                     help
                 }
             },
+            hidden: false,
+            min_args: 0,
+            max_args: None,
+            persona: None,
+            positionals: 0,
+            react: Vec::new(),
+            reactions: Vec::new(),
             response_type: CommandResponse::default(),
+            run: Vec::new(),
+            combine: false,
+            scrape: None,
             trigger: self.options.names[0].to_string(),
+            unfurl: false,
+            usage: self.options.usage.unwrap_or_default().to_string(),
             value: String::default(),
+            variants: Vec::new(),
         }
     }
 }
@@ -98,36 +553,790 @@ impl CommandData {
         self.admin
     }
 
-    pub fn get_color(&self) -> Color {
-        self.color.clone()
+    pub fn get_auto_delete(&self) -> &AutoDelete {
+        &self.auto_delete
+    }
+
+    pub fn get_color(&self) -> Color {
+        self.color.clone()
+    }
+
+    pub fn get_files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    pub fn get_help(&self) -> &str {
+        &self.help
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.hidden
+    }
+
+    pub fn get_persona(&self) -> Option<&Persona> {
+        self.persona.as_ref()
+    }
+
+    pub fn get_reactions(&self) -> &[ReactionType] {
+        &self.reactions
+    }
+
+    pub fn get_response_type(&self) -> &CommandResponse {
+        &self.response_type
+    }
+
+    /// Whether the embed is built from the linked page's metadata.
+    pub fn unfurls(&self) -> bool {
+        self.unfurl
+    }
+
+    pub fn get_scrape(&self) -> Option<&Scrape> {
+        self.scrape.as_ref()
+    }
+
+    /// Whether the responses of the commands in `run` are sent as one message
+    pub fn combines(&self) -> bool {
+        self.combine
+    }
+
+    pub fn get_trigger(&self) -> &str {
+        &self.trigger
+    }
+
+    pub fn get_usage(&self) -> &str {
+        &self.usage
+    }
+
+    pub fn get_value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn has_variants(&self) -> bool {
+        !self.variants.is_empty()
+    }
+
+    /// The command's own target followed by its role variants' targets.
+    pub fn get_targets(&self) -> Vec<&str> {
+        let mut targets = vec![self.value.as_str()];
+        targets.extend(self.variants.iter().map(|(_, target)| target.as_str()));
+        targets
+    }
+
+    /// Picks the first variant matching one of the member's role names or ids,
+    /// falling back to the command's own target.
+    pub fn for_roles(&self, roles: &[String]) -> CommandData {
+        let mut cmd = self.clone();
+        if let Some((_, target)) = self.variants.iter().find(|(variant_roles, _)| {
+            variant_roles
+                .iter()
+                .any(|role| roles.iter().any(|r| r.eq_ignore_ascii_case(role)))
+        }) {
+            cmd.value = target.clone();
+        }
+        cmd
+    }
+
+    /// Fills `{1}`, `{2}`, ... and `{rest}` in the command's value from the
+    /// arguments following the trigger. `{rest}` receives every argument after
+    /// the highest numbered placeholder.
+    /// Returns None if the argument count is outside `min_args`/`max_args`.
+    pub fn render_value(&self, raw_args: &str) -> Option<String> {
+        let args = raw_args.split_whitespace().collect::<Vec<&str>>();
+        if args.len() < self.min_args || self.max_args.is_some_and(|max| args.len() > max) {
+            return None;
+        }
+
+        let mut value = self.value.clone();
+        for position in 1..=self.positionals {
+            let arg = args.get(position - 1).unwrap_or(&"");
+            value = value.replace(&format!("{{{}}}", position), arg);
+        }
+        let rest = args
+            .iter()
+            .skip(self.positionals)
+            .cloned()
+            .collect::<Vec<&str>>()
+            .join(" ");
+        Some(value.replace("{rest}", &rest))
+    }
+}
+
+/// Rejects macro commands that run unknown commands or run each other in a cycle.
+fn validate_macros(commands: &[CommandData]) -> Result<()> {
+    fn visit(trigger: &str, commands: &[CommandData], path: &mut Vec<String>) -> Result<()> {
+        if path.iter().any(|visited| visited == trigger) {
+            path.push(trigger.to_string());
+            anyhow::bail!("commands run each other in a cycle: {}", path.join(" -> "));
+        }
+        let cmd = match commands.iter().find(|c| c.trigger == trigger) {
+            Some(cmd) => cmd,
+            None => anyhow::bail!(
+                "command `{}` runs unknown command `{}`",
+                path.last().map(String::as_str).unwrap_or_default(),
+                trigger
+            ),
+        };
+
+        path.push(trigger.to_string());
+        for step in &cmd.run {
+            visit(step, commands, path)?;
+        }
+        path.pop();
+        Ok(())
+    }
+
+    for cmd in commands {
+        visit(&cmd.trigger, commands, &mut Vec::new())?;
+    }
+    Ok(())
+}
+
+/// Resolves a path from the config relative to the config's directory.
+fn resolve_path(config_dir: &Option<PathBuf>, path: &str) -> PathBuf {
+    match config_dir {
+        Some(dir) => dir.join(path),
+        None => PathBuf::from(path),
+    }
+}
+
+/// highest `{n}` placeholder a command target can use, so other numbers in braces stay literal
+const MAX_POSITIONALS: usize = 9;
+
+/// Finds the highest `{1}` to `{9}` placeholder referenced by a command target.
+fn highest_positional(template: &str) -> usize {
+    let mut highest = 0;
+    for piece in template.split('{').skip(1) {
+        if let Some(end) = piece.find('}') {
+            let number = &piece[..end];
+            if !number.bytes().all(|b| b.is_ascii_digit()) || number.starts_with('0') {
+                continue;
+            }
+            if let Ok(position) = number.parse::<usize>() {
+                if position <= MAX_POSITIONALS {
+                    highest = highest.max(position);
+                }
+            }
+        }
+    }
+    highest
+}
+
+/// Auto-responder for chat messages that aren't commands
+#[derive(Clone, Debug)]
+pub struct ResponderData {
+    channels: Vec<u64>,
+    cooldown_secs: u64,
+    pattern: Regex,
+    react: Vec<String>,
+    reactions: Vec<ReactionType>,
+    response_type: CommandResponse,
+    value: String,
+}
+
+impl ResponderData {
+    /// Whether the responder is active in the given channel
+    pub fn watches(&self, channel_id: u64) -> bool {
+        self.channels.is_empty() || self.channels.contains(&channel_id)
+    }
+
+    pub fn matches(&self, content: &str) -> bool {
+        self.pattern.is_match(content)
+    }
+
+    pub fn get_pattern(&self) -> &str {
+        self.pattern.as_str()
+    }
+
+    pub fn get_cooldown_secs(&self) -> u64 {
+        self.cooldown_secs
+    }
+
+    pub fn get_reactions(&self) -> &[ReactionType] {
+        &self.reactions
+    }
+
+    pub fn get_response_type(&self) -> &CommandResponse {
+        &self.response_type
+    }
+
+    pub fn get_value(&self) -> &str {
+        &self.value
+    }
+}
+
+/// When a scheduled announcement is due
+#[derive(Clone, Debug)]
+pub enum Timing {
+    Cron(Box<cron::Schedule>),
+    Interval(chrono::Duration),
+}
+
+/// What a scheduled announcement or timer posts
+#[derive(Clone, Debug)]
+pub enum ScheduledMessage {
+    Text(String),
+    /// trigger of a config command whose response is posted
+    Command(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct ScheduleData {
+    channels: Vec<u64>,
+    message: ScheduledMessage,
+    name: String,
+    timezone: Tz,
+    timing: Timing,
+}
+
+impl ScheduleData {
+    pub fn get_channels(&self) -> &[u64] {
+        &self.channels
+    }
+
+    pub fn get_message(&self) -> &ScheduledMessage {
+        &self.message
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// First time the announcement is due after `last_run`.
+    pub fn next_after(&self, last_run: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match &self.timing {
+            Timing::Cron(schedule) => schedule
+                .after(&last_run.with_timezone(&self.timezone))
+                .next()
+                .map(|next| next.with_timezone(&Utc)),
+            Timing::Interval(interval) => Some(last_run + *interval),
+        }
+    }
+}
+
+/// minutes between checks of a feed when `interval_mins` isn't set
+const DEFAULT_FEED_INTERVAL_MINS: u64 = 15;
+
+/// message posted for a new feed item when `template` isn't set
+const DEFAULT_FEED_TEMPLATE: &str = "{title}\n{link}";
+
+#[derive(Clone, Debug)]
+pub struct FeedData {
+    channels: Vec<u64>,
+    interval: std::time::Duration,
+    template: String,
+    url: String,
+}
+
+impl FeedData {
+    pub fn get_channels(&self) -> &[u64] {
+        &self.channels
+    }
+
+    pub fn get_interval(&self) -> std::time::Duration {
+        self.interval
+    }
+
+    pub fn get_url(&self) -> &str {
+        &self.url
+    }
+
+    /// Fills the template's `{feed}`, `{title}`, `{link}` and `{author}` placeholders.
+    pub fn render(&self, feed: &str, title: &str, link: &str, author: &str) -> String {
+        self.template
+            .replace("{feed}", feed)
+            .replace("{title}", title)
+            .replace("{link}", link)
+            .replace("{author}", author)
+    }
+}
+
+/// Posts a rotating message into a channel that has been active enough
+#[derive(Clone, Debug)]
+pub struct TimerData {
+    channel: u64,
+    interval: std::time::Duration,
+    min_messages: u64,
+    random: bool,
+    /// texts first, then config commands
+    messages: Vec<ScheduledMessage>,
+}
+
+impl TimerData {
+    pub fn get_channel(&self) -> u64 {
+        self.channel
+    }
+
+    pub fn get_interval(&self) -> std::time::Duration {
+        self.interval
+    }
+
+    pub fn get_min_messages(&self) -> u64 {
+        self.min_messages
+    }
+
+    pub fn is_random(&self) -> bool {
+        self.random
+    }
+
+    pub fn get_messages(&self) -> &[ScheduledMessage] {
+        &self.messages
+    }
+
+    /// Tells timers apart across reloads, since they have no name.
+    pub fn key(&self) -> String {
+        format!("{}:{:?}", self.channel, self.messages)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct LinkFilterData {
+    /// punycode domains, links elsewhere are caught when this isn't empty
+    allow: Vec<String>,
+    /// punycode domains
+    deny: Vec<String>,
+    block_invites: bool,
+    exempt_roles: Vec<String>,
+    exempt_channels: Vec<u64>,
+    action: FilterAction,
+    log_channel: Option<u64>,
+}
+
+impl LinkFilterData {
+    pub fn get_allow(&self) -> &[String] {
+        &self.allow
+    }
+
+    pub fn get_deny(&self) -> &[String] {
+        &self.deny
+    }
+
+    pub fn blocks_invites(&self) -> bool {
+        self.block_invites
+    }
+
+    pub fn get_exempt_roles(&self) -> &[String] {
+        &self.exempt_roles
+    }
+
+    pub fn watches(&self, channel_id: u64) -> bool {
+        !self.exempt_channels.contains(&channel_id)
+    }
+
+    pub fn get_action(&self) -> &FilterAction {
+        &self.action
+    }
+
+    pub fn get_log_channel(&self) -> Option<u64> {
+        self.log_channel
+    }
+}
+
+/// A word list or pattern of the word filter, compiled
+#[derive(Clone, Debug)]
+struct FilterTerm {
+    regex: Regex,
+    action: WordAction,
+}
+
+#[derive(Clone, Debug)]
+pub struct WordFilterData {
+    terms: Vec<FilterTerm>,
+    /// actions for a user's first, second, ... strike within the window
+    escalation: Vec<WordAction>,
+    strike_window: std::time::Duration,
+    timeout_role: Option<String>,
+    timeout: std::time::Duration,
+    exempt_roles: Vec<String>,
+    exempt_channels: Vec<u64>,
+    log_channel: Option<u64>,
+}
+
+impl WordFilterData {
+    /// The harshest action among the terms found in `content`, if any are.
+    pub fn check(&self, content: &str) -> Option<WordAction> {
+        self.terms
+            .iter()
+            .filter(|term| term.regex.is_match(content))
+            .map(|term| term.action)
+            .max()
+    }
+
+    /// actions for a member's first, second, ... strike within the window
+    pub fn get_escalation(&self) -> &[WordAction] {
+        &self.escalation
+    }
+
+    pub fn get_strike_window(&self) -> std::time::Duration {
+        self.strike_window
+    }
+
+    pub fn get_timeout_role(&self) -> Option<&str> {
+        self.timeout_role.as_deref()
+    }
+
+    pub fn get_timeout(&self) -> std::time::Duration {
+        self.timeout
+    }
+
+    pub fn get_exempt_roles(&self) -> &[String] {
+        &self.exempt_roles
+    }
+
+    pub fn watches(&self, channel_id: u64) -> bool {
+        !self.exempt_channels.contains(&channel_id)
+    }
+
+    pub fn get_log_channel(&self) -> Option<u64> {
+        self.log_channel
+    }
+}
+
+/// Message posted when a member joins or leaves
+#[derive(Clone, Debug)]
+pub struct GreetingData {
+    channel: u64,
+    color: Color,
+    dm: Option<String>,
+    embed: bool,
+    message: String,
+}
+
+impl GreetingData {
+    fn new(greeting: Greeting) -> Self {
+        Self {
+            channel: greeting.channel,
+            color: greeting.color.unwrap_or_default(),
+            dm: greeting.dm,
+            embed: greeting.embed.unwrap_or(false),
+            message: greeting.message,
+        }
+    }
+
+    pub fn get_channel(&self) -> u64 {
+        self.channel
+    }
+
+    pub fn get_color(&self) -> Color {
+        self.color.clone()
+    }
+
+    pub fn get_dm(&self) -> Option<&str> {
+        self.dm.as_deref()
+    }
+
+    pub fn is_embed(&self) -> bool {
+        self.embed
+    }
+
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+}
+
+/// When a burst of joins looks like a raid, and what lockdown does about it
+#[derive(Clone, Debug)]
+pub struct AntiRaidData {
+    join_threshold: usize,
+    suspicious_threshold: usize,
+    join_window: std::time::Duration,
+    min_account_age: Option<chrono::Duration>,
+    flag_default_avatar: bool,
+    verification_level: VerificationLevel,
+    audit_channel: Option<u64>,
+    alert_users: Vec<u64>,
+}
+
+impl AntiRaidData {
+    pub fn get_join_threshold(&self) -> usize {
+        self.join_threshold
+    }
+
+    pub fn get_suspicious_threshold(&self) -> usize {
+        self.suspicious_threshold
+    }
+
+    pub fn get_join_window(&self) -> std::time::Duration {
+        self.join_window
+    }
+
+    /// true if an account looks like a throwaway: created too recently,
+    /// or still on a default avatar if those are flagged
+    pub fn is_suspicious(&self, user: &serenity::model::user::User) -> bool {
+        let too_new = self
+            .min_account_age
+            .is_some_and(|age| Utc::now() - user.id.created_at() < age);
+        too_new || (self.flag_default_avatar && user.avatar.is_none())
+    }
+
+    pub fn get_verification_level(&self) -> VerificationLevel {
+        self.verification_level
+    }
+
+    pub fn get_audit_channel(&self) -> Option<u64> {
+        self.audit_channel
+    }
+
+    pub fn get_alert_users(&self) -> &[u64] {
+        &self.alert_users
+    }
+}
+
+/// A role given to members who react to a message with an emoji
+#[derive(Clone, Debug)]
+pub struct ReactionRoleData {
+    guild_id: u64,
+    channel_id: u64,
+    message_id: u64,
+    emoji: String,
+    /// custom emoji are resolved once the cache is ready
+    reaction: Option<ReactionType>,
+    role: String,
+    mode: ReactionRoleMode,
+}
+
+impl ReactionRoleData {
+    pub fn get_guild_id(&self) -> u64 {
+        self.guild_id
+    }
+
+    pub fn get_channel_id(&self) -> u64 {
+        self.channel_id
+    }
+
+    pub fn get_message_id(&self) -> u64 {
+        self.message_id
+    }
+
+    pub fn get_link(&self) -> String {
+        format!(
+            "https://discord.com/channels/{}/{}/{}",
+            self.guild_id, self.channel_id, self.message_id
+        )
+    }
+
+    pub fn get_emoji(&self) -> &str {
+        &self.emoji
+    }
+
+    pub fn get_reaction(&self) -> Option<&ReactionType> {
+        self.reaction.as_ref()
+    }
+
+    pub fn get_role(&self) -> &str {
+        &self.role
+    }
+
+    pub fn get_mode(&self) -> ReactionRoleMode {
+        self.mode
+    }
+
+    /// true if a member reacted with this binding's emoji
+    pub fn matches(&self, emoji: &ReactionType) -> bool {
+        match emoji {
+            // the emoji variation selector is left out by some clients
+            ReactionType::Unicode(unicode) => {
+                unicode.trim_end_matches('\u{FE0F}') == self.emoji.trim_end_matches('\u{FE0F}')
+            }
+            ReactionType::Custom { name, .. } => name.as_deref() == Some(self.emoji.as_str()),
+            _ => false,
+        }
+    }
+}
+
+/// Guild, channel and message id of a message link as copied in Discord.
+fn parse_message_link(link: &str) -> Option<(u64, u64, u64)> {
+    let url = reqwest::Url::parse(link.trim()).ok()?;
+    let host = url.host_str()?;
+    let host = host
+        .trim_start_matches("ptb.")
+        .trim_start_matches("canary.");
+    if host != "discord.com" && host != "discordapp.com" {
+        return None;
+    }
+    let mut segments = url.path_segments()?;
+    if segments.next()? != "channels" {
+        return None;
+    }
+    let ids: Vec<u64> = segments.map(|id| id.parse().ok()).collect::<Option<_>>()?;
+    match ids[..] {
+        [guild, channel, message] => Some((guild, channel, message)),
+        _ => None,
+    }
+}
+
+/// An action taken once a member has `count` warnings
+#[derive(Clone, Debug)]
+pub struct ThresholdData {
+    count: usize,
+    action: WarnAction,
+    timeout: std::time::Duration,
+}
+
+impl ThresholdData {
+    pub fn get_action(&self) -> WarnAction {
+        self.action
+    }
+
+    pub fn get_timeout(&self) -> std::time::Duration {
+        self.timeout
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct WarningsData {
+    expiry: Option<chrono::Duration>,
+    dm: bool,
+    timeout_role: Option<String>,
+    log_channel: Option<u64>,
+    /// ordered by count
+    thresholds: Vec<ThresholdData>,
+}
+
+impl Default for WarningsData {
+    fn default() -> Self {
+        WarningsData {
+            expiry: None,
+            dm: true,
+            timeout_role: None,
+            log_channel: None,
+            thresholds: Vec::new(),
+        }
+    }
+}
+
+impl WarningsData {
+    /// How long warnings count toward thresholds, forever if `None`.
+    pub fn get_expiry(&self) -> Option<chrono::Duration> {
+        self.expiry
+    }
+
+    /// Whether warned members are told by DM.
+    pub fn dms_member(&self) -> bool {
+        self.dm
+    }
+
+    pub fn get_timeout_role(&self) -> Option<&str> {
+        self.timeout_role.as_deref()
+    }
+
+    pub fn get_log_channel(&self) -> Option<u64> {
+        self.log_channel
+    }
+
+    /// The highest threshold a member with `count` warnings has reached, so
+    /// members past the last threshold keep getting its action.
+    pub fn threshold_for(&self, count: usize) -> Option<&ThresholdData> {
+        self.thresholds.iter().rev().find(|t| t.count <= count)
+    }
+}
+
+/// Fills a greeting's `{user}`, `{name}`, `{server}` and `{member_count}` placeholders.
+pub fn render_greeting(
+    template: &str,
+    user: &serenity::model::user::User,
+    server: &str,
+    member_count: u64,
+) -> String {
+    template
+        .replace("{user}", &user.mention().to_string())
+        .replace("{name}", &user.name)
+        .replace("{server}", server)
+        .replace("{member_count}", &member_count.to_string())
+}
+
+/// A stream on a given day, in the schedule's timezone
+#[derive(Clone, Debug)]
+pub struct StreamTime {
+    start: NaiveTime,
+    duration: Option<chrono::Duration>,
+    title: Option<String>,
+}
+
+impl StreamTime {
+    fn new(
+        start: &str,
+        duration_mins: Option<u64>,
+        title: Option<String>,
+    ) -> Result<Self, chrono::ParseError> {
+        Ok(Self {
+            start: NaiveTime::parse_from_str(start, "%H:%M")?,
+            duration: duration_mins.map(|mins| chrono::Duration::minutes(mins as i64)),
+            title,
+        })
+    }
+
+    pub fn get_start(&self) -> NaiveTime {
+        self.start
+    }
+
+    pub fn get_duration(&self) -> Option<chrono::Duration> {
+        self.duration
     }
 
-    pub fn get_help(&self) -> &str {
-        &self.help
+    pub fn get_title(&self) -> Option<&str> {
+        self.title.as_deref()
     }
+}
 
-    pub fn get_response_type(&self) -> &CommandResponse {
-        &self.response_type
+#[derive(Clone, Debug)]
+pub struct StreamScheduleData {
+    timezone: Tz,
+    weekly: Vec<(Weekday, StreamTime)>,
+    /// dates whose weekly streams are replaced, by nothing when cancelled
+    overrides: Vec<(NaiveDate, Option<StreamTime>)>,
+}
+
+impl StreamScheduleData {
+    pub fn get_timezone(&self) -> Tz {
+        self.timezone
     }
 
-    pub fn get_trigger(&self) -> &str {
-        &self.trigger
+    pub fn get_weekly(&self) -> &[(Weekday, StreamTime)] {
+        &self.weekly
     }
 
-    pub fn get_value(&self) -> &str {
-        &self.value
+    pub fn get_overrides(&self) -> &[(NaiveDate, Option<StreamTime>)] {
+        &self.overrides
     }
 }
 
 #[derive(Debug, Default)]
 pub struct ConfigData {
+    auto_delete: AutoDelete,
+    command_prefix: String,
     commands: Vec<CommandData>,
+    responders: Vec<ResponderData>,
+    schedules: Vec<ScheduleData>,
+    feeds: Vec<FeedData>,
+    timers: Vec<TimerData>,
+    reaction_roles: Vec<ReactionRoleData>,
+    welcome: Option<GreetingData>,
+    goodbye: Option<GreetingData>,
+    stream_schedule: Option<StreamScheduleData>,
+    link_filter: Option<LinkFilterData>,
+    word_filter: Option<WordFilterData>,
+    anti_raid: Option<AntiRaidData>,
     help_color: Color,
     help_message: String,
+    help_response_type: CommandResponse,
+    help_title: String,
+    help_footer: Option<String>,
+    help_thumbnail: Option<String>,
+    help_author: Option<String>,
+    help_persona: Option<Persona>,
+    reminder_response_type: CommandResponse,
+    link_check_hours: Option<u64>,
+    moderator_roles: Vec<String>,
+    warnings: WarningsData,
     site_url: String,
+    timezone: Option<Tz>,
 }
 
 impl ConfigData {
+    pub fn get_auto_delete(&self) -> &AutoDelete {
+        &self.auto_delete
+    }
+
     pub fn get_help_color(&self) -> &Color {
         &self.help_color
     }
@@ -136,20 +1345,203 @@ impl ConfigData {
         &self.help_message
     }
 
+    pub fn get_help_response_type(&self) -> &CommandResponse {
+        &self.help_response_type
+    }
+
+    pub fn get_help_title(&self) -> &str {
+        &self.help_title
+    }
+
+    pub fn get_help_footer(&self) -> Option<&str> {
+        self.help_footer.as_deref()
+    }
+
+    pub fn get_help_thumbnail(&self) -> Option<&str> {
+        self.help_thumbnail.as_deref()
+    }
+
+    pub fn get_help_author(&self) -> Option<&str> {
+        self.help_author.as_deref()
+    }
+
+    pub fn get_help_persona(&self) -> Option<&Persona> {
+        self.help_persona.as_ref()
+    }
+
     pub fn get_commands(&self) -> &Vec<CommandData> {
         &self.commands
     }
 
+    /// Hours between link checks that DM the owner about broken links, if they're enabled.
+    pub fn get_link_check_hours(&self) -> Option<u64> {
+        self.link_check_hours
+    }
+
+    /// Role names or ids whose members can use the moderator commands.
+    pub fn get_moderator_roles(&self) -> &[String] {
+        &self.moderator_roles
+    }
+
+    pub fn get_warnings(&self) -> &WarningsData {
+        &self.warnings
+    }
+
+    /// Either `dm` or `channel`.
+    pub fn get_reminder_response_type(&self) -> &CommandResponse {
+        &self.reminder_response_type
+    }
+
+    /// Timezone that times of day typed by users are read in, UTC unless configured.
+    pub fn get_timezone(&self) -> Tz {
+        self.timezone.unwrap_or(Tz::UTC)
+    }
+
+    pub fn get_command_prefix(&self) -> &str {
+        &self.command_prefix
+    }
+
+    pub fn get_responders(&self) -> &Vec<ResponderData> {
+        &self.responders
+    }
+
+    pub fn get_schedules(&self) -> &Vec<ScheduleData> {
+        &self.schedules
+    }
+
+    pub fn get_feeds(&self) -> &Vec<FeedData> {
+        &self.feeds
+    }
+
+    pub fn get_timers(&self) -> &Vec<TimerData> {
+        &self.timers
+    }
+
+    pub fn get_reaction_roles(&self) -> &[ReactionRoleData] {
+        &self.reaction_roles
+    }
+
+    pub fn get_welcome(&self) -> Option<&GreetingData> {
+        self.welcome.as_ref()
+    }
+
+    pub fn get_goodbye(&self) -> Option<&GreetingData> {
+        self.goodbye.as_ref()
+    }
+
+    pub fn get_stream_schedule(&self) -> Option<&StreamScheduleData> {
+        self.stream_schedule.as_ref()
+    }
+
+    pub fn get_link_filter(&self) -> Option<&LinkFilterData> {
+        self.link_filter.as_ref()
+    }
+
+    pub fn get_word_filter(&self) -> Option<&WordFilterData> {
+        self.word_filter.as_ref()
+    }
+
+    pub fn get_anti_raid(&self) -> Option<&AntiRaidData> {
+        self.anti_raid.as_ref()
+    }
+
+    /// Lists the commands a macro command runs, in order, with nested macros expanded.
+    /// Cycles are rejected when the config is loaded.
+    pub fn expand_macro(&self, cmd: &CommandData) -> Vec<CommandData> {
+        let mut expanded = Vec::new();
+        for trigger in &cmd.run {
+            if let Some(step) = self.commands.iter().find(|c| &c.trigger == trigger) {
+                if step.run.is_empty() {
+                    expanded.push(step.clone());
+                } else {
+                    expanded.extend(self.expand_macro(step));
+                }
+            }
+        }
+        expanded
+    }
+
     pub fn get_site_url(&self) -> &str {
         &self.site_url
     }
+
+    /// Looks up the custom emoji named in the commands' `react` lists
+    /// in the cached guilds. Unicode emoji don't need the cache.
+    pub async fn resolve_reactions(&mut self, cache: &Cache) -> Result<()> {
+        let mut guild_emojis = std::collections::HashMap::new();
+        for guild_id in cache.guilds().await {
+            if let Some(guild) = cache.guild(guild_id).await {
+                for emoji in guild.emojis.values() {
+                    guild_emojis.insert(emoji.name.clone(), emoji.clone());
+                }
+            }
+        }
+
+        let mut unknown = Vec::new();
+        let mut resolve = |names: &Vec<String>, owner: &str| {
+            let mut reactions = Vec::new();
+            for name in names {
+                if !is_custom_emoji_name(name) {
+                    reactions.push(ReactionType::Unicode(name.clone()));
+                } else if let Some(emoji) = guild_emojis.get(name) {
+                    reactions.push(emoji.clone().into());
+                } else {
+                    unknown.push(format!("`{}` in `{}`", name, owner));
+                }
+            }
+            reactions
+        };
+        for cmd in self.commands.iter_mut() {
+            cmd.reactions = resolve(&cmd.react, &cmd.trigger);
+        }
+        for responder in self.responders.iter_mut() {
+            responder.reactions = resolve(&responder.react, responder.pattern.as_str());
+        }
+        for binding in self.reaction_roles.iter_mut() {
+            let link = binding.get_link();
+            binding.reaction = resolve(&vec![binding.emoji.clone()], &link).pop();
+        }
+
+        if unknown.is_empty() {
+            Ok(())
+        } else {
+            anyhow::bail!("Unknown custom emoji: {}", unknown.join(", "))
+        }
+    }
+}
+
+/// Custom emoji are referenced by name, unicode emoji are used as is.
+fn is_custom_emoji_name(emoji: &str) -> bool {
+    emoji.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Unicode emoji from a `react` list, custom emoji need the cache to be resolved.
+fn unicode_reactions(react: &[String]) -> Vec<ReactionType> {
+    react
+        .iter()
+        .filter(|emoji| !is_custom_emoji_name(emoji))
+        .map(|emoji| ReactionType::Unicode(emoji.clone()))
+        .collect()
 }
 
 impl Config {
     /// Consumes Config which has private, optional members for serialization
     /// and returns ConfigData struct which has public, non-optional members.
-    pub async fn data(self) -> ConfigData {
-        ConfigData {
+    /// Fails if a command references files that are missing or too large.
+    pub async fn data(self) -> Result<ConfigData> {
+        let timezone = match &self.timezone {
+            Some(name) => match name.parse::<Tz>() {
+                Ok(tz) => Some(tz),
+                Err(e) => anyhow::bail!("timezone: {}", e),
+            },
+            None => None,
+        };
+        let auto_delete = AutoDelete {
+            after_secs: self.delete_after_secs,
+            trigger: self.delete_trigger.unwrap_or(false),
+        };
+
+        let mut data = ConfigData {
             help_color: {
                 match self.help_color {
                     Some(color) => color,
@@ -162,12 +1554,85 @@ impl Config {
                     None => String::default(),
                 }
             },
+            help_response_type: self.help_response_type.unwrap_or(CommandResponse::Embed),
+            help_title: self.help_title.unwrap_or_else(|| String::from("Muffetbot")),
+            help_footer: self.help_footer,
+            help_thumbnail: self.help_thumbnail,
+            help_author: self.help_author,
+            help_persona: self.help_persona,
+            reminder_response_type: match self.reminder_response_type {
+                None => CommandResponse::Dm,
+                Some(CommandResponse::Dm) => CommandResponse::Dm,
+                Some(CommandResponse::Channel) => CommandResponse::Channel,
+                Some(_) => anyhow::bail!("`reminder_response_type` must be `dm` or `channel`"),
+            },
+            timezone,
+            link_check_hours: match self.link_check_hours {
+                Some(0) => anyhow::bail!("`link_check_hours` must be positive"),
+                hours => hours,
+            },
+            stream_schedule: match self.stream_schedule {
+                Some(schedule) => {
+                    let timezone = match schedule.timezone {
+                        Some(name) => match name.parse::<Tz>() {
+                            Ok(tz) => tz,
+                            Err(e) => anyhow::bail!("stream_schedule timezone: {}", e),
+                        },
+                        None => timezone.unwrap_or(Tz::UTC),
+                    };
+
+                    let mut weekly = Vec::new();
+                    for slot in schedule.slots.unwrap_or_default() {
+                        let day = match slot.day.parse::<Weekday>() {
+                            Ok(day) => day,
+                            Err(_) => anyhow::bail!("stream_schedule: `{}` is not a day", slot.day),
+                        };
+                        match StreamTime::new(&slot.start, slot.duration_mins, slot.title) {
+                            Ok(time) => weekly.push((day, time)),
+                            Err(e) => {
+                                anyhow::bail!("stream_schedule start `{}`: {}", slot.start, e)
+                            }
+                        }
+                    }
+
+                    let mut overrides = Vec::new();
+                    for change in schedule.overrides.unwrap_or_default() {
+                        let date = match NaiveDate::parse_from_str(&change.date, "%Y-%m-%d") {
+                            Ok(date) => date,
+                            Err(e) => {
+                                anyhow::bail!("stream_schedule date `{}`: {}", change.date, e)
+                            }
+                        };
+                        let time = match &change.start {
+                            Some(start) => {
+                                match StreamTime::new(start, change.duration_mins, change.title) {
+                                    Ok(time) => Some(time),
+                                    Err(e) => {
+                                        anyhow::bail!("stream_schedule start `{}`: {}", start, e)
+                                    }
+                                }
+                            }
+                            None => None,
+                        };
+                        overrides.push((date, time));
+                    }
+
+                    Some(StreamScheduleData {
+                        timezone,
+                        weekly,
+                        overrides,
+                    })
+                }
+                None => None,
+            },
             commands: {
                 let mut commands = Vec::new();
                 if let Some(conf_commands) = self.commands {
                     for cmd in conf_commands {
                         let admin = cmd.admin.unwrap_or(false);
-                        let help = if admin {
+                        let hidden = cmd.hidden.unwrap_or(false);
+                        let trigger = cmd.name.trim().to_lowercase();
+                        let mut help = if admin {
                             String::from("**admin command**\n")
                                 + cmd
                                     .help
@@ -177,10 +1642,50 @@ impl Config {
                             cmd.help
                                 .unwrap_or("No help available for this command".to_string())
                         };
-                        let response_type = cmd.response_type.unwrap_or_default();
+                        if let Some(usage) = &cmd.usage {
+                            help += &format!("\n{}", usage);
+                        }
+                        let response_type = match (cmd.channel, cmd.response_type) {
+                            (Some(ids), None) | (Some(ids), Some(CommandResponse::Channel)) => {
+                                CommandResponse::Channels(ids.into())
+                            }
+                            (Some(_), Some(other)) => anyhow::bail!(
+                                "command `{}`: `channel` posts plain messages and can't be combined with `response_type = \"{}\"`",
+                                trigger,
+                                other
+                            ),
+                            (None, response_type) => response_type.unwrap_or_default(),
+                        };
                         let color = cmd.color.unwrap_or_default();
+                        let auto_delete = AutoDelete {
+                            after_secs: cmd.delete_after_secs.or(auto_delete.after_secs),
+                            trigger: cmd.delete_trigger.unwrap_or(auto_delete.trigger),
+                        };
+                        if let Some(scrape) = &cmd.scrape {
+                            if let Err(e) = reqwest::Url::parse(&scrape.url) {
+                                anyhow::bail!("command `{}`: scrape url: {}", trigger, e);
+                            }
+                            if let Err(e) = scraper::Selector::parse(&scrape.selector) {
+                                anyhow::bail!("command `{}`: scrape selector: {:?}", trigger, e);
+                            }
+                        }
+                        let unfurl = cmd.unfurl.unwrap_or(false);
+                        if unfurl && matches!(response_type, CommandResponse::Channels(_)) {
+                            anyhow::bail!(
+                                "command `{}`: `unfurl` needs an embed, which `channel` can't post",
+                                trigger
+                            );
+                        }
+                        if unfurl && response_type != CommandResponse::Embed {
+                            anyhow::bail!(
+                                "command `{}`: `unfurl` needs `response_type = \"embed\"`",
+                                trigger
+                            );
+                        }
                         let value = if let Some(target) = cmd.target {
                             target
+                        } else if cmd.scrape.is_some() {
+                            String::from("{scraped}")
                         } else if let Some(mut url_path) = cmd.url_path {
                             if !url_path.starts_with("/") {
                                 url_path = String::from("/") + url_path.as_ref();
@@ -191,29 +1696,515 @@ impl Config {
                                 None => url_path,
                             };
                             path
+                        } else if cmd.react.is_some() || cmd.run.is_some() || cmd.variants.is_some()
+                        {
+                            String::new()
                         } else {
                             continue;
                         };
 
+                        let mut files = Vec::new();
+                        for file in cmd.files.unwrap_or_default() {
+                            let path = resolve_path(&self.config_dir, &file);
+                            let size = match std::fs::metadata(&path) {
+                                Ok(meta) if meta.is_file() => meta.len(),
+                                _ => anyhow::bail!(
+                                    "command `{}`: file `{}` not found",
+                                    trigger,
+                                    path.display()
+                                ),
+                            };
+                            if size > MAX_FILE_SIZE {
+                                anyhow::bail!(
+                                    "command `{}`: file `{}` is larger than 8MB",
+                                    trigger,
+                                    path.display()
+                                );
+                            }
+                            files.push(path);
+                        }
+
+                        let variants = cmd
+                            .variants
+                            .unwrap_or_default()
+                            .into_iter()
+                            .map(|variant| (variant.roles, variant.target))
+                            .collect::<Vec<(Vec<String>, String)>>();
+                        let positionals = variants
+                            .iter()
+                            .map(|(_, target)| highest_positional(target))
+                            .fold(highest_positional(&value), usize::max);
+                        let min_args = cmd.min_args.unwrap_or(positionals);
+                        let max_args = cmd.max_args;
+                        let usage = match cmd.usage {
+                            Some(usage) => usage,
+                            None => match max_args {
+                                Some(max) if max == min_args => {
+                                    format!("`{}` takes {} argument(s)", trigger, max)
+                                }
+                                Some(max) => {
+                                    format!("`{}` takes {} to {} arguments", trigger, min_args, max)
+                                }
+                                None => {
+                                    format!("`{}` takes at least {} argument(s)", trigger, min_args)
+                                }
+                            },
+                        };
+
                         commands.push(CommandData {
                             admin,
+                            auto_delete,
                             color,
+                            files,
+                            hidden,
+                            min_args,
+                            max_args,
+                            persona: cmd.persona,
+                            positionals,
+                            reactions: unicode_reactions(cmd.react.as_deref().unwrap_or_default()),
+                            react: cmd.react.unwrap_or_default(),
                             response_type,
-                            trigger: cmd.name.trim().to_lowercase(),
+                            run: cmd
+                                .run
+                                .unwrap_or_default()
+                                .iter()
+                                .map(|name| name.trim().to_lowercase())
+                                .collect(),
+                            combine: cmd.combine.unwrap_or(false),
+                            scrape: cmd.scrape,
+                            trigger,
+                            unfurl,
+                            usage,
                             value,
+                            variants,
                             help,
                         });
                     }
                 }
+                validate_macros(&commands)?;
                 commands
             },
+            responders: {
+                let mut responders = Vec::new();
+                for responder in self.responders.unwrap_or_default() {
+                    let pattern = if let Some(regex) = responder.regex {
+                        regex
+                    } else if let Some(contains) = responder.contains {
+                        regex::escape(&contains)
+                    } else if let Some(starts_with) = responder.starts_with {
+                        format!("^{}", regex::escape(&starts_with))
+                    } else {
+                        anyhow::bail!("responders need `contains`, `starts_with` or `regex`");
+                    };
+                    let pattern = RegexBuilder::new(&pattern)
+                        .case_insensitive(!responder.case_sensitive.unwrap_or(false))
+                        .build()?;
+                    let react = responder.react.unwrap_or_default();
+                    if responder.target.is_none() && react.is_empty() {
+                        anyhow::bail!("responder `{}` needs a `target` or `react`", pattern);
+                    }
+                    let response_type = responder.response_type.unwrap_or(CommandResponse::Reply);
+                    if matches!(
+                        response_type,
+                        CommandResponse::Embed | CommandResponse::DmOwner
+                    ) {
+                        anyhow::bail!(
+                            "responder `{}` can't use `response_type = \"{}\"`, only `reply`, `channel`, `dm` or `channel:<ids>`",
+                            pattern,
+                            response_type
+                        );
+                    }
+
+                    responders.push(ResponderData {
+                        channels: responder.channels.unwrap_or_default(),
+                        cooldown_secs: responder.cooldown_secs.unwrap_or(60),
+                        pattern,
+                        reactions: unicode_reactions(&react),
+                        react,
+                        response_type,
+                        value: responder.target.unwrap_or_default(),
+                    });
+                }
+                responders
+            },
+            schedules: {
+                let mut schedules = Vec::new();
+                for schedule in self.schedules.unwrap_or_default() {
+                    if schedules
+                        .iter()
+                        .any(|s: &ScheduleData| s.name == schedule.name)
+                    {
+                        anyhow::bail!("more than one schedule is named `{}`", schedule.name);
+                    }
+                    let timing = match (schedule.cron, schedule.interval_mins) {
+                        (Some(expression), None) => match cron::Schedule::from_str(&expression) {
+                            Ok(cron) => Timing::Cron(Box::new(cron)),
+                            Err(e) => anyhow::bail!("schedule `{}`: {}", schedule.name, e),
+                        },
+                        (None, Some(mins)) if mins > 0 => {
+                            Timing::Interval(chrono::Duration::minutes(mins as i64))
+                        }
+                        _ => anyhow::bail!(
+                            "schedule `{}` needs either `cron` or a positive `interval_mins`",
+                            schedule.name
+                        ),
+                    };
+                    let timezone = match schedule.timezone {
+                        Some(name) => match name.parse::<Tz>() {
+                            Ok(tz) => tz,
+                            Err(e) => anyhow::bail!("schedule `{}`: {}", schedule.name, e),
+                        },
+                        None => Tz::UTC,
+                    };
+                    let message = match (schedule.target, schedule.command) {
+                        (Some(target), None) => ScheduledMessage::Text(target),
+                        (None, Some(command)) => {
+                            ScheduledMessage::Command(command.trim().to_lowercase())
+                        }
+                        _ => anyhow::bail!(
+                            "schedule `{}` needs either a `target` or a `command`",
+                            schedule.name
+                        ),
+                    };
+
+                    schedules.push(ScheduleData {
+                        channels: schedule.channel.into(),
+                        message,
+                        name: schedule.name,
+                        timezone,
+                        timing,
+                    });
+                }
+                schedules
+            },
+            feeds: {
+                let mut feeds = Vec::new();
+                for feed in self.feeds.unwrap_or_default() {
+                    if let Err(e) = reqwest::Url::parse(&feed.url) {
+                        anyhow::bail!("feed `{}`: {}", feed.url, e);
+                    }
+                    if feeds.iter().any(|f: &FeedData| f.url == feed.url) {
+                        anyhow::bail!("feed `{}` is listed more than once", feed.url);
+                    }
+                    let interval_mins = match feed.interval_mins {
+                        Some(0) => {
+                            anyhow::bail!("feed `{}` needs a positive `interval_mins`", feed.url)
+                        }
+                        Some(mins) => mins,
+                        None => DEFAULT_FEED_INTERVAL_MINS,
+                    };
+
+                    feeds.push(FeedData {
+                        channels: feed.channel.into(),
+                        interval: std::time::Duration::from_secs(interval_mins * 60),
+                        template: feed
+                            .template
+                            .unwrap_or_else(|| DEFAULT_FEED_TEMPLATE.to_string()),
+                        url: feed.url,
+                    });
+                }
+                feeds
+            },
+            timers: {
+                let mut timers = Vec::new();
+                for timer in self.timers.unwrap_or_default() {
+                    if timer.interval_mins == 0 {
+                        anyhow::bail!(
+                            "timer in {} needs a positive `interval_mins`",
+                            timer.channel
+                        );
+                    }
+                    let mut messages: Vec<ScheduledMessage> = timer
+                        .messages
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(ScheduledMessage::Text)
+                        .collect();
+                    messages.extend(
+                        timer
+                            .commands
+                            .unwrap_or_default()
+                            .iter()
+                            .map(|cmd| ScheduledMessage::Command(cmd.trim().to_lowercase())),
+                    );
+                    if messages.is_empty() {
+                        anyhow::bail!("timer in {} has no `messages` or `commands`", timer.channel);
+                    }
+
+                    timers.push(TimerData {
+                        channel: timer.channel,
+                        interval: std::time::Duration::from_secs(timer.interval_mins * 60),
+                        min_messages: timer.min_messages.unwrap_or(0),
+                        random: timer.random.unwrap_or(false),
+                        messages,
+                    });
+                }
+                timers
+            },
+            link_filter: match self.link_filter {
+                Some(filter) => {
+                    let action = filter.action.unwrap_or_default();
+                    if action == FilterAction::Log && filter.log_channel.is_none() {
+                        anyhow::bail!("link_filter: `action = \"log\"` needs a `log_channel`");
+                    }
+                    Some(LinkFilterData {
+                        allow: filter
+                            .allow
+                            .unwrap_or_default()
+                            .iter()
+                            .map(|domain| filter_domain(domain))
+                            .collect::<Result<_>>()?,
+                        deny: filter
+                            .deny
+                            .unwrap_or_default()
+                            .iter()
+                            .map(|domain| filter_domain(domain))
+                            .collect::<Result<_>>()?,
+                        block_invites: filter.block_invites.unwrap_or(false),
+                        exempt_roles: filter.exempt_roles.unwrap_or_default(),
+                        exempt_channels: filter.exempt_channels.unwrap_or_default(),
+                        action,
+                        log_channel: filter.log_channel,
+                    })
+                }
+                None => None,
+            },
+            word_filter: match self.word_filter {
+                Some(filter) => {
+                    let action = filter.action.unwrap_or_default();
+                    let mut terms = Vec::new();
+                    let words = filter.words.unwrap_or_default();
+                    if words.iter().any(|word| word.trim().is_empty()) {
+                        anyhow::bail!("word_filter: `words` can't contain empty words");
+                    }
+                    if !words.is_empty() {
+                        terms.push(FilterTerm {
+                            regex: word_list_regex(&words)?,
+                            action,
+                        });
+                    }
+                    let rules = filter
+                        .patterns
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|pattern| (pattern, action))
+                        .chain(filter.rules.unwrap_or_default().into_iter().map(|rule| {
+                            let rule_action = rule.action.unwrap_or(action);
+                            (rule.pattern, rule_action)
+                        }));
+                    for (pattern, action) in rules {
+                        let regex = match RegexBuilder::new(&pattern).case_insensitive(true).build()
+                        {
+                            Ok(regex) => regex,
+                            Err(e) => anyhow::bail!("word_filter: pattern `{}`: {}", pattern, e),
+                        };
+                        terms.push(FilterTerm { regex, action });
+                    }
+
+                    let escalation = filter.escalation.unwrap_or_default();
+                    let times_out = terms
+                        .iter()
+                        .map(|term| &term.action)
+                        .chain(escalation.iter())
+                        .any(|action| *action == WordAction::Timeout);
+                    if times_out && filter.timeout_role.is_none() {
+                        anyhow::bail!("word_filter: `timeout` actions need a `timeout_role`");
+                    }
+                    if filter.strike_window_mins == Some(0) || filter.timeout_mins == Some(0) {
+                        anyhow::bail!(
+                            "word_filter: `strike_window_mins` and `timeout_mins` must be positive"
+                        );
+                    }
+                    Some(WordFilterData {
+                        terms,
+                        escalation,
+                        strike_window: std::time::Duration::from_secs(
+                            filter.strike_window_mins.unwrap_or(60) * 60,
+                        ),
+                        timeout_role: filter.timeout_role,
+                        timeout: std::time::Duration::from_secs(
+                            filter.timeout_mins.unwrap_or(10) * 60,
+                        ),
+                        exempt_roles: filter.exempt_roles.unwrap_or_default(),
+                        exempt_channels: filter.exempt_channels.unwrap_or_default(),
+                        log_channel: filter.log_channel,
+                    })
+                }
+                None => None,
+            },
+            anti_raid: match self.anti_raid {
+                Some(anti_raid) => {
+                    let verification_level = match anti_raid.verification_level.as_deref() {
+                        Some("low") => VerificationLevel::Low,
+                        Some("medium") => VerificationLevel::Medium,
+                        Some("high") | None => VerificationLevel::High,
+                        Some("higher") => VerificationLevel::Higher,
+                        Some(other) => anyhow::bail!(
+                            "anti_raid: unknown verification level `{}`, use `low`, `medium`, `high` or `higher`",
+                            other
+                        ),
+                    };
+                    let join_threshold = anti_raid.join_threshold.unwrap_or(10);
+                    let suspicious_threshold = anti_raid.suspicious_threshold.unwrap_or(5);
+                    if join_threshold == 0
+                        || suspicious_threshold == 0
+                        || anti_raid.join_window_secs == Some(0)
+                    {
+                        anyhow::bail!(
+                            "anti_raid: thresholds and `join_window_secs` must be positive"
+                        );
+                    }
+                    Some(AntiRaidData {
+                        join_threshold,
+                        suspicious_threshold,
+                        join_window: std::time::Duration::from_secs(
+                            anti_raid.join_window_secs.unwrap_or(60),
+                        ),
+                        min_account_age: match anti_raid.min_account_age_hours.unwrap_or(24) {
+                            0 => None,
+                            hours => Some(chrono::Duration::hours(hours as i64)),
+                        },
+                        flag_default_avatar: anti_raid.flag_default_avatar.unwrap_or(true),
+                        verification_level,
+                        audit_channel: anti_raid.audit_channel,
+                        alert_users: anti_raid.alert_users.unwrap_or_default(),
+                    })
+                }
+                None => None,
+            },
+            reaction_roles: {
+                let mut reaction_roles = Vec::new();
+                for binding in self.reaction_roles.unwrap_or_default() {
+                    let (guild_id, channel_id, message_id) =
+                        match parse_message_link(&binding.message) {
+                            Some(ids) => ids,
+                            None => anyhow::bail!(
+                                "reaction_role: `{}` is not a message link",
+                                binding.message
+                            ),
+                        };
+                    if reaction_roles.iter().any(|r: &ReactionRoleData| {
+                        r.message_id == message_id && r.emoji == binding.emoji
+                    }) {
+                        anyhow::bail!(
+                            "reaction_role: {} is bound more than once on {}",
+                            binding.emoji,
+                            binding.message
+                        );
+                    }
+                    reaction_roles.push(ReactionRoleData {
+                        guild_id,
+                        channel_id,
+                        message_id,
+                        reaction: unicode_reactions(std::slice::from_ref(&binding.emoji)).pop(),
+                        emoji: binding.emoji,
+                        role: binding.role,
+                        mode: binding.mode.unwrap_or_default(),
+                    });
+                }
+                reaction_roles
+            },
+            moderator_roles: self.moderator_roles.unwrap_or_default(),
+            warnings: match self.warnings {
+                Some(warnings) => {
+                    let mut thresholds = Vec::new();
+                    for threshold in warnings.thresholds.unwrap_or_default() {
+                        if threshold.count == 0 {
+                            anyhow::bail!("warnings: threshold `count` must be positive");
+                        }
+                        if thresholds
+                            .iter()
+                            .any(|t: &ThresholdData| t.count == threshold.count)
+                        {
+                            anyhow::bail!(
+                                "warnings: more than one threshold at {} warnings",
+                                threshold.count
+                            );
+                        }
+                        if threshold.action == WarnAction::Timeout
+                            && warnings.timeout_role.is_none()
+                        {
+                            anyhow::bail!("warnings: `timeout` thresholds need a `timeout_role`");
+                        }
+                        thresholds.push(ThresholdData {
+                            count: threshold.count,
+                            action: threshold.action,
+                            timeout: std::time::Duration::from_secs(
+                                threshold.timeout_mins.unwrap_or(60) * 60,
+                            ),
+                        });
+                    }
+                    thresholds.sort_by_key(|t| t.count);
+                    WarningsData {
+                        expiry: match warnings.expire_days {
+                            Some(0) => anyhow::bail!("warnings: `expire_days` must be positive"),
+                            days => days.map(|days| chrono::Duration::days(days as i64)),
+                        },
+                        dm: warnings.dm.unwrap_or(true),
+                        timeout_role: warnings.timeout_role,
+                        log_channel: warnings.log_channel,
+                        thresholds,
+                    }
+                }
+                None => WarningsData::default(),
+            },
+            welcome: self.welcome.map(GreetingData::new),
+            goodbye: match self.goodbye {
+                // members who left usually can't be messaged anymore
+                Some(goodbye) if goodbye.dm.is_some() => {
+                    anyhow::bail!("`dm` can only be set for [welcome]")
+                }
+                goodbye => goodbye.map(GreetingData::new),
+            },
+            command_prefix: match &self.command_prefix {
+                Some(prefix) => prefix.to_string(),
+                None => String::from("!"),
+            },
+            auto_delete,
             site_url: {
                 match self.site_url {
                     Some(url) => url,
                     None => String::new(),
                 }
             },
+        };
+
+        for schedule in &data.schedules {
+            if let ScheduledMessage::Command(trigger) = &schedule.message {
+                if !data.commands.iter().any(|cmd| &cmd.trigger == trigger) {
+                    anyhow::bail!(
+                        "schedule `{}` posts unknown command `{}`",
+                        schedule.name,
+                        trigger
+                    );
+                }
+            }
+        }
+        for timer in &data.timers {
+            for message in &timer.messages {
+                if let ScheduledMessage::Command(trigger) = message {
+                    if !data.commands.iter().any(|cmd| &cmd.trigger == trigger) {
+                        anyhow::bail!(
+                            "timer in {} posts unknown command `{}`",
+                            timer.channel,
+                            trigger
+                        );
+                    }
+                }
+            }
+        }
+
+        // the cache is only populated once the bot is running, so reactions
+        // are resolved on `cache_ready` instead when this is the initial load.
+        // Unknown emoji are skipped like they are there, so reloads still work
+        if let Some(cache) = crate::CACHE.get() {
+            if !cache.guilds().await.is_empty() {
+                if let Err(e) = data.resolve_reactions(cache).await {
+                    info!("{}", e);
+                }
+            }
         }
+        Ok(data)
     }
 
     /// Getter for path to the logs directory.
@@ -231,6 +2222,21 @@ impl Config {
         }
     }
 
+    /// Getter for the help command's name followed by its aliases.
+    /// Cannot be hot reloaded.
+    pub fn get_help_names(&self) -> Vec<String> {
+        let mut names = vec![match &self.help_command {
+            Some(name) => name.trim().to_lowercase(),
+            None => String::from("help"),
+        }];
+        if let Some(aliases) = &self.help_aliases {
+            for alias in aliases {
+                names.push(alias.trim().to_lowercase());
+            }
+        }
+        names
+    }
+
     /// Getter for discord token (Panics if no token).
     /// Cannot be hot reloaded.
     pub fn get_token(&self) -> String {
@@ -264,12 +2270,27 @@ impl Config {
         };
         cmds.push(Command {
             admin: Some(false),
+            channel: None,
             color: Some(Color::default()),
+            delete_after_secs: None,
+            delete_trigger: None,
+            files: None,
             name: command_name.to_owned(),
+            persona: None,
+            react: None,
+            run: None,
+            combine: None,
+            scrape: None,
+            variants: None,
             help: None,
+            hidden: None,
+            min_args: None,
+            max_args: None,
             response_type: Some(CommandResponse::default()),
             target: Some(command_target.to_owned()),
+            unfurl: None,
             url_path: None,
+            usage: None,
         });
         self.commands = Some(cmds);
 
@@ -300,23 +2321,109 @@ impl Config {
             Err(())
         }
     }
+
+    /// Attempts to add a word, or a `/pattern/`, to the word filter,
+    /// creating the filter if there is none yet -
+    /// Supports hot reload.
+    pub async fn push_filter_term(&mut self, term: &str) -> Result<(), ()> {
+        let filter = self.word_filter.get_or_insert_with(WordFilter::default);
+        let (list, term) = match filter_pattern(term) {
+            Some(pattern) => (filter.patterns.get_or_insert_with(Vec::new), pattern),
+            None => (filter.words.get_or_insert_with(Vec::new), term.trim()),
+        };
+        if term.is_empty() || list.iter().any(|t| t.eq_ignore_ascii_case(term)) {
+            return Err(());
+        }
+        list.push(term.to_string());
+        Ok(())
+    }
+
+    /// Attempts to remove a word, or a `/pattern/`, from the word filter -
+    /// Supports hot reload.
+    pub async fn pop_filter_term(&mut self, term: &str) -> Result<(), ()> {
+        let filter = self.word_filter.as_mut().ok_or(())?;
+        let (list, term) = match filter_pattern(term) {
+            Some(pattern) => (filter.patterns.as_mut(), pattern),
+            None => (filter.words.as_mut(), term.trim()),
+        };
+        let list = list.ok_or(())?;
+        let before = list.len();
+        list.retain(|t| !t.eq_ignore_ascii_case(term));
+        if list.len() < before {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+
+    /// Attempts to bind an emoji on a message to a role -
+    /// Supports hot reload.
+    pub async fn push_reaction_role(
+        &mut self,
+        message: &str,
+        emoji: &str,
+        role: &str,
+    ) -> Result<(), ()> {
+        let ids = parse_message_link(message).ok_or(())?;
+        let bindings = self.reaction_roles.get_or_insert_with(Vec::new);
+        if bindings
+            .iter()
+            .any(|r| parse_message_link(&r.message) == Some(ids) && r.emoji == emoji)
+        {
+            return Err(());
+        }
+        bindings.push(ReactionRole {
+            message: message.to_string(),
+            emoji: emoji.to_string(),
+            role: role.to_string(),
+            mode: None,
+        });
+        Ok(())
+    }
+
+    /// Attempts to remove an emoji's role binding from a message -
+    /// Supports hot reload.
+    pub async fn pop_reaction_role(&mut self, message: &str, emoji: &str) -> Result<(), ()> {
+        let ids = parse_message_link(message).ok_or(())?;
+        let bindings = self.reaction_roles.as_mut().ok_or(())?;
+        let before = bindings.len();
+        bindings.retain(|r| !(parse_message_link(&r.message) == Some(ids) && r.emoji == emoji));
+        if bindings.len() < before {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+}
+
+/// The pattern in a word filter term written as `/pattern/`
+fn filter_pattern(term: &str) -> Option<&str> {
+    let term = term.trim();
+    if term.len() > 2 && term.starts_with('/') && term.ends_with('/') {
+        Some(&term[1..term.len() - 1])
+    } else {
+        None
+    }
 }
 
 use std::fs::read_to_string;
 use std::path::{Path, PathBuf};
 /// Attempts to fetch config asynchronously
 pub async fn get_conf<P: AsRef<Path>>(config_path: P) -> Result<Config> {
-    let conf_file = read_to_string(config_path)?;
-    let conf_toml: Config = toml::from_str(&conf_file)?;
+    let conf_file = read_to_string(&config_path)?;
+    let mut conf_toml: Config = toml::from_str(&conf_file)?;
+    conf_toml.config_dir = config_path.as_ref().parent().map(Path::to_path_buf);
 
     Ok(conf_toml)
 }
 
-/// Attempts to write new config to file asynchronously
+/// Attempts to write new config to file asynchronously.
+/// Nothing is written if the new config is invalid.
 pub async fn hot_reload_conf<P: AsRef<Path>>(config_path: P, new_config: Config) -> Result<()> {
-    std::fs::write(&config_path, toml::to_string(&new_config)?)?;
+    let serialized = toml::to_string(&new_config)?;
+    let reloaded_data = new_config.data().await?;
+    std::fs::write(&config_path, serialized)?;
 
-    let reloaded_data = new_config.data().await;
     *crate::CONFIG.lock().await = reloaded_data;
     Ok(())
 }
@@ -477,11 +2584,38 @@ pub fn init() -> Result<String, InitError> {
     let new_config = Config {
         help_color: Some(Color::BlitzBlue),
         commands: None,
+        responders: None,
+        schedules: None,
+        feeds: None,
+        timers: None,
+        welcome: None,
+        goodbye: None,
+        stream_schedule: None,
+        link_filter: None,
+        word_filter: None,
+        anti_raid: None,
         command_prefix,
+        delete_after_secs: None,
+        delete_trigger: None,
         discord_token,
         help_message,
+        help_command: None,
+        help_aliases: None,
+        help_response_type: None,
+        help_title: None,
+        help_footer: None,
+        help_thumbnail: None,
+        help_author: None,
+        help_persona: None,
         log_path: log_path.to_string_lossy().to_string(),
         site_url,
+        timezone: None,
+        reminder_response_type: None,
+        link_check_hours: None,
+        moderator_roles: None,
+        warnings: None,
+        reaction_roles: None,
+        config_dir: None,
     };
 
     let conf_path = Path::new(&config_path);
@@ -559,3 +2693,265 @@ impl Into<Colour> for Color {
         Colour::new(self as u32)
     }
 }
+
+/// A config with the required values and the given tables, for tests.
+#[cfg(test)]
+pub(crate) fn test_config(tables: &str) -> Config {
+    let toml = format!("discord_token = \"x\"\nlog_path = \"logs\"\n{}", tables);
+    toml::from_str(&toml).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn command(tables: &str) -> CommandData {
+        test_config(tables).data().await.unwrap().get_commands()[0].clone()
+    }
+
+    #[test]
+    fn highest_positional_counts_placeholders() {
+        assert_eq!(highest_positional("no placeholders"), 0);
+        assert_eq!(highest_positional("{1} and {3} {rest}"), 3);
+        assert_eq!(highest_positional("{2}{1}"), 2);
+    }
+
+    #[test]
+    fn highest_positional_ignores_other_numbers() {
+        assert_eq!(highest_positional("price {0} {10}"), 0);
+        assert_eq!(highest_positional("{01} {+2} { 3} {9999}"), 0);
+        assert_eq!(highest_positional("{1} {99999999999999999999999}"), 1);
+    }
+
+    #[tokio::test]
+    async fn render_value_fills_positionals_and_rest() {
+        let cmd = command(
+            r#"
+            [[command]]
+            name = "shoutout"
+            target = "Go show {1} some love! {rest}"
+            "#,
+        )
+        .await;
+        assert_eq!(
+            cmd.render_value(" @muffet  you rock ").as_deref(),
+            Some("Go show @muffet some love! you rock")
+        );
+        assert_eq!(
+            cmd.render_value("@muffet").as_deref(),
+            Some("Go show @muffet some love! ")
+        );
+        assert_eq!(cmd.render_value(""), None);
+    }
+
+    #[tokio::test]
+    async fn channel_rejects_other_response_types() {
+        let error = test_config(
+            r#"
+            [[command]]
+            name = "golive"
+            target = "live!"
+            channel = 123
+            response_type = "embed"
+            "#,
+        )
+        .data()
+        .await
+        .unwrap_err();
+        assert!(error.to_string().contains("`channel` posts plain messages"));
+
+        let error = test_config(
+            r#"
+            [[command]]
+            name = "golive"
+            target = "https://example.com"
+            channel = [123, 456]
+            unfurl = true
+            "#,
+        )
+        .data()
+        .await
+        .unwrap_err();
+        assert!(error.to_string().contains("`unfurl` needs an embed"));
+
+        let cmd = command(
+            r#"
+            [[command]]
+            name = "golive"
+            target = "live!"
+            channel = 123
+            response_type = "channel"
+            "#,
+        )
+        .await;
+        assert_eq!(
+            cmd.get_response_type(),
+            &CommandResponse::Channels(vec![123])
+        );
+    }
+
+    #[tokio::test]
+    async fn responders_reject_unsupported_response_types() {
+        for response_type in &["embed", "dm owner"] {
+            let error = test_config(&format!(
+                "[[responder]]\ncontains = \"mail\"\ntarget = \"PO box\"\nresponse_type = \"{}\"",
+                response_type
+            ))
+            .data()
+            .await
+            .unwrap_err();
+            assert!(error.to_string().contains("can't use `response_type"));
+        }
+        let data = test_config(
+            "[[responder]]\ncontains = \"mail\"\ntarget = \"PO box\"\nresponse_type = \"dm\"",
+        )
+        .data()
+        .await
+        .unwrap();
+        assert_eq!(
+            data.get_responders()[0].get_response_type(),
+            &CommandResponse::Dm
+        );
+    }
+
+    #[tokio::test]
+    async fn render_value_checks_arity() {
+        let cmd = command(
+            r#"
+            [[command]]
+            name = "pair"
+            target = "{1} & {2}, price {10}"
+            max_args = 2
+            "#,
+        )
+        .await;
+        assert_eq!(cmd.render_value("a"), None);
+        assert_eq!(
+            cmd.render_value("a b").as_deref(),
+            Some("a & b, price {10}")
+        );
+        assert_eq!(cmd.render_value("a b c"), None);
+    }
+
+    #[tokio::test]
+    async fn macros_reject_running_themselves() {
+        let error = test_config(
+            r#"
+            [[command]]
+            name = "socials"
+            run = ["twitter", "socials"]
+
+            [[command]]
+            name = "twitter"
+            target = "https://twitter.com/muffet"
+            "#,
+        )
+        .data()
+        .await
+        .unwrap_err();
+        assert!(error.to_string().contains("cycle: socials -> socials"));
+    }
+
+    #[tokio::test]
+    async fn macros_reject_indirect_cycles() {
+        let error = test_config(
+            r#"
+            [[command]]
+            name = "a"
+            run = ["b"]
+
+            [[command]]
+            name = "b"
+            run = ["a"]
+            "#,
+        )
+        .data()
+        .await
+        .unwrap_err();
+        assert!(error.to_string().contains("cycle: a -> b -> a"));
+    }
+
+    #[tokio::test]
+    async fn macros_reject_unknown_commands() {
+        let error = test_config(
+            r#"
+            [[command]]
+            name = "socials"
+            run = ["twitter", "myspace"]
+
+            [[command]]
+            name = "twitter"
+            target = "https://twitter.com/muffet"
+            "#,
+        )
+        .data()
+        .await
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("command `socials` runs unknown command `myspace`"));
+
+        let cmd = command(
+            r#"
+            [[command]]
+            name = "socials"
+            run = ["Twitter"]
+
+            [[command]]
+            name = "twitter"
+            target = "https://twitter.com/muffet"
+            "#,
+        )
+        .await;
+        assert_eq!(cmd.run, vec!["twitter"]);
+    }
+
+    #[test]
+    fn parse_message_link_reads_discord_links() {
+        assert_eq!(
+            parse_message_link("https://discord.com/channels/1/22/333"),
+            Some((1, 22, 333))
+        );
+        assert_eq!(
+            parse_message_link(" https://discordapp.com/channels/1/22/333 "),
+            Some((1, 22, 333))
+        );
+    }
+
+    #[test]
+    fn parse_message_link_accepts_ptb_and_canary() {
+        assert_eq!(
+            parse_message_link("https://ptb.discord.com/channels/1/22/333"),
+            Some((1, 22, 333))
+        );
+        assert_eq!(
+            parse_message_link("https://canary.discord.com/channels/1/22/333"),
+            Some((1, 22, 333))
+        );
+    }
+
+    #[test]
+    fn parse_message_link_rejects_other_links() {
+        assert_eq!(parse_message_link("not a link"), None);
+        assert_eq!(
+            parse_message_link("https://example.com/channels/1/22/333"),
+            None
+        );
+        assert_eq!(
+            parse_message_link("https://discord.com/channels/1/22"),
+            None
+        );
+        assert_eq!(
+            parse_message_link("https://discord.com/channels/1/22/333/4"),
+            None
+        );
+        assert_eq!(
+            parse_message_link("https://discord.com/channels/@me/22/333"),
+            None
+        );
+        assert_eq!(
+            parse_message_link("https://discord.com/invite/1/22/333"),
+            None
+        );
+    }
+}
@@ -3,7 +3,7 @@ use serenity::{
     client::bridge::gateway::ShardManager,
     framework::standard::{
         macros::{help, hook},
-        CommandGroup, HelpOptions,
+        CommandGroup, HelpCommand, HelpOptions,
     },
     model::id::UserId,
 };
@@ -18,7 +18,18 @@ impl TypeMapKey for ShardManagerContainer {
 }
 
 use crate::prelude::*;
-use crate::utils::config::{CommandData, ConfigData};
+use crate::utils::config::{CommandData, ConfigData, Persona};
+use crate::utils::moderation::is_moderator;
+use crate::utils::split::{split_embed, split_message, FIELD_LIMIT};
+use crate::utils::web::{find_links, link_card, scrape, LinkCard};
+use crate::utils::webhooks::{persona_embeds, persona_say};
+use serde_json::Value;
+use serenity::builder::CreateEmbed;
+use serenity::{
+    http::Http,
+    model::{channel::Embed, id::ChannelId},
+    utils::{content_safe, ContentSafeOptions},
+};
 
 #[instrument]
 #[help]
@@ -33,17 +44,21 @@ pub async fn muffet_help(
     let borrowed_config = &crate::CONFIG.lock().await;
     let mut caught_error: Result<(), serenity::framework::standard::CommandError> = Ok(());
 
+    let moderator = is_moderator(ctx, msg, borrowed_config.get_moderator_roles()).await;
     let mut commands: Vec<CommandData> = vec![];
     for group in groups {
         for cmd in group.options.commands {
             if cmd.options.owners_only && owners.get(&msg.author.id).is_none() {
                 continue;
             }
+            if std::ptr::eq(*group, &crate::moderators::MODERATORS_GROUP) && !moderator {
+                continue;
+            }
             commands.push((*cmd).into());
         }
     }
     for cmd in borrowed_config.get_commands() {
-        if cmd.restricted() && owners.get(&msg.author.id).is_none() {
+        if cmd.is_hidden() || (cmd.restricted() && owners.get(&msg.author.id).is_none()) {
             continue;
         }
         commands.push(cmd.clone());
@@ -54,9 +69,9 @@ pub async fn muffet_help(
         for cmd in &commands {
             if next_arg == cmd.get_trigger() {
                 caught_error = if cmd.restricted() {
-                    embedded_pm(ctx, msg, cmd).await
+                    embedded_pm(ctx, msg, cmd, borrowed_config.get_auto_delete()).await
                 } else {
-                    single_help(ctx, msg, cmd).await
+                    single_help(ctx, msg, borrowed_config, cmd).await
                 };
                 break;
             }
@@ -71,34 +86,195 @@ pub async fn muffet_help(
     Ok(())
 }
 
-async fn embedded_cmd(ctx: &Context, msg: &Message, cmd_data: &CommandData) -> CommandResult {
-    msg.channel_id
-        .send_message(ctx, |m| {
-            m.embed(|embed| {
-                embed.colour(cmd_data.get_color());
-                embed.field(cmd_data.get_trigger(), cmd_data.get_value(), true);
-                embed
-            });
-            m
-        })
-        .await?;
+async fn embedded_cmd(
+    ctx: &Context,
+    msg: &Message,
+    cmd_data: &CommandData,
+    value: &str,
+    auto_delete: &AutoDelete,
+) -> CommandResult {
+    let card = unfurl(cmd_data, value).await;
+    let sent = send_embeds(&ctx.http, msg.channel_id, cmd_data, value, card.as_ref()).await?;
+    clean_up(ctx, msg, sent, auto_delete).await;
     Ok(())
 }
 
-async fn embedded_pm(ctx: &Context, msg: &Message, cmd_data: &CommandData) -> CommandResult {
-    msg.author
+/// sends a config command's response as embeds in its color, or as a single
+/// link card when the command unfurled its link
+async fn send_embeds(
+    http: &Http,
+    channel_id: ChannelId,
+    cmd_data: &CommandData,
+    value: &str,
+    card: Option<&Unfurled>,
+) -> serenity::Result<Vec<Message>> {
+    if let Some(card) = card {
+        let sent = channel_id
+            .send_message(http, |m| {
+                m.embed(|embed| {
+                    card_embed(embed, cmd_data, card);
+                    embed
+                });
+                m.add_files(cmd_data.get_files())
+            })
+            .await?;
+        return Ok(vec![sent]);
+    }
+
+    let mut sent = Vec::new();
+    for (i, page) in split_embed(value).iter().enumerate() {
+        let files = if i == 0 { cmd_data.get_files() } else { &[] };
+        sent.push(
+            channel_id
+                .send_message(http, |m| {
+                    m.embed(|embed| {
+                        embed.colour(cmd_data.get_color());
+                        embed_page(embed, cmd_data.get_trigger(), page, i == 0);
+                        embed
+                    });
+                    m.add_files(files)
+                })
+                .await?,
+        );
+    }
+    Ok(sent)
+}
+
+/// Longest embed description Discord accepts
+const DESCRIPTION_LIMIT: usize = 2048;
+
+/// an `unfurl` command's link with the linked page's metadata
+struct Unfurled {
+    url: String,
+    /// the rest of the response, shown above the page's description
+    text: String,
+    card: LinkCard,
+}
+
+/// fetches the link card of the first link in an `unfurl` command's response.
+/// None if the command doesn't unfurl or the page has no usable metadata
+async fn unfurl(cmd_data: &CommandData, value: &str) -> Option<Unfurled> {
+    if !cmd_data.unfurls() {
+        return None;
+    }
+    let url = find_links(value).into_iter().next()?;
+
+    match link_card(&url).await {
+        Ok(card) => Some(Unfurled {
+            text: value
+                .replace(&format!("<{}>", url), "")
+                .replace(&url, "")
+                .trim()
+                .to_string(),
+            url,
+            card,
+        }),
+        Err(e) => {
+            info!("Unfurling `{}` failed: {}", cmd_data.get_trigger(), e);
+            None
+        }
+    }
+}
+
+/// fills `embed` with a link card, in the page's theme color if it has one
+fn card_embed(embed: &mut CreateEmbed, cmd_data: &CommandData, unfurled: &Unfurled) {
+    let card = &unfurled.card;
+    embed.url(&unfurled.url);
+    embed.title(card.get_title().unwrap_or(&unfurled.url));
+    let description = [Some(unfurled.text.as_str()), card.get_description()]
+        .iter()
+        .flatten()
+        .filter(|part| !part.is_empty())
+        .cloned()
+        .collect::<Vec<&str>>()
+        .join("\n\n");
+    if !description.is_empty() {
+        embed.description(
+            description
+                .chars()
+                .take(DESCRIPTION_LIMIT)
+                .collect::<String>(),
+        );
+    }
+    if let Some(image) = card.get_image() {
+        embed.image(image);
+    }
+    if let Some(site_name) = card.get_site_name() {
+        embed.footer(|f| f.text(site_name));
+    }
+    match card.get_color() {
+        Some(color) => embed.colour(color),
+        None => embed.colour(cmd_data.get_color()),
+    };
+}
+
+/// renders a config command's response for the given arguments, filling in
+/// `{scraped}` from the web. None if the arguments don't fit the command
+pub async fn render_response(cmd_data: &CommandData, raw_args: &str) -> Option<String> {
+    let value = cmd_data.render_value(raw_args)?;
+    let scrape_settings = match cmd_data.get_scrape() {
+        Some(scrape_settings) => scrape_settings,
+        None => return Some(value),
+    };
+
+    let scraped = match scrape(scrape_settings).await {
+        Ok(scraped) => scraped,
+        Err(e) => {
+            info!("Scrape for `{}` failed: {}", cmd_data.get_trigger(), e);
+            String::from("*unavailable right now*")
+        }
+    };
+    Some(value.replace("{scraped}", &scraped))
+}
+
+/// posts a config command's response into a channel without a triggering message,
+/// as embeds in the command's color for `embed` commands
+pub async fn post_command(
+    http: &Http,
+    channel_id: ChannelId,
+    cmd_data: &CommandData,
+) -> serenity::Result<Vec<Message>> {
+    let value = match render_response(cmd_data, "").await {
+        Some(value) => value,
+        None => cmd_data.get_value().to_string(),
+    };
+    if *cmd_data.get_response_type() != CommandResponse::Embed {
+        return say_split(http, channel_id, &value, cmd_data.get_files()).await;
+    }
+
+    let card = unfurl(cmd_data, &value).await;
+    send_embeds(http, channel_id, cmd_data, &value, card.as_ref()).await
+}
+
+async fn embedded_pm(
+    ctx: &Context,
+    msg: &Message,
+    cmd_data: &CommandData,
+    auto_delete: &AutoDelete,
+) -> CommandResult {
+    let sent = msg
+        .author
         .direct_message(ctx, |m| {
             m.embed(|embed| {
                 embed.colour(cmd_data.get_color());
-                embed.field(cmd_data.get_trigger(), &cmd_data.get_help(), true);
+                embed_page(embed, cmd_data.get_trigger(), &help_page(cmd_data), true);
                 embed
             });
             m
         })
         .await?;
+    clean_up(ctx, msg, vec![sent], auto_delete).await;
     Ok(())
 }
 
+/// a command's help text, cut down to what fits into a single embed
+fn help_page(cmd_data: &CommandData) -> Vec<String> {
+    split_embed(cmd_data.get_help())
+        .into_iter()
+        .next()
+        .unwrap_or_default()
+}
+
 fn flatten_cmds(cmds: &Vec<CommandData>) -> String {
     let mut flattened = String::new();
     for cmd in cmds {
@@ -113,50 +289,375 @@ async fn echo_group_cmds(
     config_data: &ConfigData,
     cmds: &Vec<CommandData>,
 ) -> CommandResult {
-    msg.channel_id
-        .send_message(&ctx, |m| {
-            m.embed(|embed| {
-                embed.colour(config_data.get_help_color().clone());
-                embed.description(config_data.get_help_message());
-                embed.field("Muffetbot", flatten_cmds(cmds), true);
-                embed
-            });
-            m
-        })
-        .await?;
-    Ok(())
+    use CommandResponse::*;
+    match config_data.get_help_response_type() {
+        Channel | Channels(_) | Reply => {
+            let listing = format!(
+                "{}\n**{}**\n{}",
+                config_data.get_help_message(),
+                config_data.get_help_title(),
+                flatten_cmds(cmds)
+            );
+            if let Some(persona) = config_data.get_help_persona() {
+                let response_type = config_data.get_help_response_type();
+                if let Some(sent) =
+                    respond_as(ctx, msg, persona, response_type, &listing, vec![]).await
+                {
+                    clean_up(ctx, msg, sent, config_data.get_auto_delete()).await;
+                    return Ok(());
+                }
+            }
+            announce_with_cleanup(
+                ctx,
+                msg,
+                listing,
+                config_data.get_help_response_type(),
+                config_data.get_auto_delete(),
+                &[],
+            )
+            .await
+        }
+        Dm | DmOwner => {
+            let sent = msg
+                .author
+                .direct_message(&ctx, |m| {
+                    m.embed(|embed| {
+                        help_embed(embed, config_data, cmds);
+                        embed
+                    });
+                    m
+                })
+                .await?;
+            clean_up(ctx, msg, vec![sent], config_data.get_auto_delete()).await;
+            Ok(())
+        }
+        Embed => {
+            if let Some(persona) = config_data.get_help_persona() {
+                let embed = serenity::model::channel::Embed::fake(|embed| {
+                    help_embed(embed, config_data, cmds);
+                    embed
+                });
+                if let Some(sent) = respond_as(ctx, msg, persona, &Embed, "", vec![embed]).await {
+                    clean_up(ctx, msg, sent, config_data.get_auto_delete()).await;
+                    return Ok(());
+                }
+            }
+            let sent = msg
+                .channel_id
+                .send_message(&ctx, |m| {
+                    m.embed(|embed| {
+                        help_embed(embed, config_data, cmds);
+                        embed
+                    });
+                    m
+                })
+                .await?;
+            clean_up(ctx, msg, vec![sent], config_data.get_auto_delete()).await;
+            Ok(())
+        }
+    }
 }
 
-async fn single_help(ctx: &Context, msg: &Message, cmd_data: &CommandData) -> CommandResult {
-    msg.channel_id
-        .send_message(&ctx, |m| {
-            m.embed(|embed| {
-                embed.colour(cmd_data.get_color());
-                embed.field(cmd_data.get_trigger(), cmd_data.get_help(), true);
-                embed
-            });
-            m
-        })
-        .await?;
-    Ok(())
+/// fills the help listing embed with the configured color, title, footer, thumbnail and author
+fn help_embed(embed: &mut CreateEmbed, config_data: &ConfigData, cmds: &Vec<CommandData>) {
+    embed.colour(config_data.get_help_color().clone());
+    embed.description(config_data.get_help_message());
+    let listing = split_message(&flatten_cmds(cmds), FIELD_LIMIT);
+    embed_page(embed, config_data.get_help_title(), &listing, true);
+    if let Some(footer) = config_data.get_help_footer() {
+        embed.footer(|f| f.text(footer));
+    }
+    if let Some(thumbnail) = config_data.get_help_thumbnail() {
+        embed.thumbnail(thumbnail);
+    }
+    if let Some(author) = config_data.get_help_author() {
+        embed.author(|a| a.name(author));
+    }
+}
+
+async fn single_help(
+    ctx: &Context,
+    msg: &Message,
+    config_data: &ConfigData,
+    cmd_data: &CommandData,
+) -> CommandResult {
+    use CommandResponse::*;
+    match config_data.get_help_response_type() {
+        Channel | Channels(_) | Reply => {
+            let help = format!("**{}**\n{}", cmd_data.get_trigger(), cmd_data.get_help());
+            announce_with_cleanup(
+                ctx,
+                msg,
+                help,
+                config_data.get_help_response_type(),
+                config_data.get_auto_delete(),
+                &[],
+            )
+            .await
+        }
+        Dm | DmOwner => embedded_pm(ctx, msg, cmd_data, config_data.get_auto_delete()).await,
+        Embed => {
+            let sent = msg
+                .channel_id
+                .send_message(&ctx, |m| {
+                    m.embed(|embed| {
+                        embed.colour(cmd_data.get_color());
+                        embed_page(embed, cmd_data.get_trigger(), &help_page(cmd_data), true);
+                        embed
+                    });
+                    m
+                })
+                .await?;
+            clean_up(ctx, msg, vec![sent], config_data.get_auto_delete()).await;
+            Ok(())
+        }
+    }
+}
+
+/// builds the help command from `MUFFET_HELP`, dispatched under the configured name and aliases
+pub fn configured_help(names: Vec<String>) -> &'static HelpCommand {
+    let names: Vec<&'static str> = names
+        .into_iter()
+        .map(|name| &*Box::leak(name.into_boxed_str()))
+        .collect();
+    let options = Box::leak(Box::new(HelpOptions {
+        names: Box::leak(names.into_boxed_slice()),
+        ..MUFFET_HELP.options.clone()
+    }));
+
+    Box::leak(Box::new(HelpCommand {
+        fun: MUFFET_HELP.fun,
+        options,
+    }))
+}
+
+/// The arguments following `prefix` and `name`, or a mention and `name`,
+/// at the start of a command message.
+fn command_args<'a>(content: &'a str, prefix: &str, name: &str) -> &'a str {
+    let content = content.trim_start();
+    let after_prefix = match content.strip_prefix(prefix) {
+        Some(rest) => rest,
+        // `<@id>` or `<@!id>`, since the bot also answers to mentions
+        None => match content.strip_prefix("<@") {
+            Some(rest) => match rest.find('>') {
+                Some(end) => rest[end + 1..].trim_start(),
+                None => return "",
+            },
+            None => return "",
+        },
+    };
+    after_prefix.strip_prefix(name).unwrap_or_default()
 }
 
 #[hook]
 #[instrument]
 pub async fn unknown_command(ctx: &Context, msg: &Message, unknown_command_name: &str) {
-    for cmd in crate::CONFIG.lock().await.get_commands() {
-        if unknown_command_name == cmd.get_trigger() {
-            if let CommandResponse::Embed = cmd.get_response_type() {
-                if let Err(e) = embedded_cmd(ctx, msg, cmd).await {
-                    info!("Config command announcement failed: {}", e);
+    let (cmd, steps, prefix) = {
+        let config = crate::CONFIG.lock().await;
+        match config
+            .get_commands()
+            .iter()
+            .find(|cmd| unknown_command_name == cmd.get_trigger())
+        {
+            Some(cmd) => (
+                cmd.clone(),
+                config.expand_macro(cmd),
+                config.get_command_prefix().to_string(),
+            ),
+            None => return,
+        }
+    };
+
+    let needs_roles = cmd.has_variants() || steps.iter().any(CommandData::has_variants);
+    let roles = match msg.guild_id {
+        Some(guild_id) if needs_roles => member_roles(ctx, guild_id, msg.author.id).await,
+        _ => vec![],
+    };
+    let cmd = cmd.for_roles(&roles);
+    let steps = steps
+        .iter()
+        .map(|step| step.for_roles(&roles))
+        .collect::<Vec<CommandData>>();
+
+    let raw_args = command_args(&msg.content, &prefix, unknown_command_name);
+    let value = match render_response(&cmd, raw_args).await {
+        Some(value) => value,
+        None => {
+            if let Err(e) = announce_with_cleanup(
+                ctx,
+                msg,
+                cmd.get_usage(),
+                &CommandResponse::Reply,
+                cmd.get_auto_delete(),
+                &[],
+            )
+            .await
+            {
+                info!("Config command usage reply failed: {}", e);
+            }
+            return;
+        }
+    };
+
+    if steps.is_empty() {
+        respond(ctx, msg, &cmd, &value, cmd.get_auto_delete()).await;
+    } else if cmd.combines() {
+        let mut combined = vec![value];
+        for step in &steps {
+            combined.extend(render_response(step, raw_args).await);
+        }
+        combined.retain(|value| !value.trim().is_empty());
+        respond(ctx, msg, &cmd, &combined.join("\n"), cmd.get_auto_delete()).await;
+    } else {
+        // the trigger is deleted once every step has responded
+        respond(
+            ctx,
+            msg,
+            &cmd,
+            &value,
+            &cmd.get_auto_delete().without_trigger(),
+        )
+        .await;
+        for step in &steps {
+            match render_response(step, raw_args).await {
+                Some(value) => {
+                    let auto_delete = step.get_auto_delete().without_trigger();
+                    respond(ctx, msg, step, &value, &auto_delete).await
                 }
-            } else {
-                if let Err(e) = announce(ctx, msg, &cmd.get_value(), &cmd.get_response_type()).await
-                {
-                    info!("Config command announcement failed: {}", e);
+                None => info!("Skipped `{}`, arguments don't fit", step.get_trigger()),
+            }
+        }
+        clean_up(ctx, msg, vec![], cmd.get_auto_delete()).await;
+    }
+}
+
+/// reacts and responds to a config command according to its response type
+async fn respond(
+    ctx: &Context,
+    msg: &Message,
+    cmd: &CommandData,
+    value: &str,
+    auto_delete: &AutoDelete,
+) {
+    for reaction in cmd.get_reactions() {
+        if let Err(e) = msg.react(ctx, reaction.clone()).await {
+            info!("Config command reaction failed: {}", e);
+        }
+    }
+    if value.trim().is_empty() {
+        return;
+    }
+
+    // personas can't upload files, so those responses are always posted by the bot
+    if let (Some(persona), true) = (cmd.get_persona(), cmd.get_files().is_empty()) {
+        let embeds = match unfurl(cmd, value).await {
+            Some(card) => vec![Embed::fake(|embed| {
+                card_embed(embed, cmd, &card);
+                embed
+            })],
+            None => split_embed(value)
+                .iter()
+                .enumerate()
+                .map(|(i, page)| {
+                    Embed::fake(|embed| {
+                        embed.colour(cmd.get_color());
+                        embed_page(embed, cmd.get_trigger(), page, i == 0);
+                        embed
+                    })
+                })
+                .collect(),
+        };
+        if let Some(sent) =
+            respond_as(ctx, msg, persona, cmd.get_response_type(), value, embeds).await
+        {
+            clean_up(ctx, msg, sent, auto_delete).await;
+            return;
+        }
+    }
+
+    if let CommandResponse::Embed = cmd.get_response_type() {
+        if let Err(e) = embedded_cmd(ctx, msg, cmd, value, auto_delete).await {
+            info!("Config command announcement failed: {}", e);
+        }
+    } else {
+        if let Err(e) = announce_with_cleanup(
+            ctx,
+            msg,
+            value,
+            cmd.get_response_type(),
+            auto_delete,
+            cmd.get_files(),
+        )
+        .await
+        {
+            info!("Config command announcement failed: {}", e);
+        }
+    }
+}
+
+/// posts through channel webhooks under the persona's name and avatar.
+/// Returns None if the response type can't be sent through a webhook, or if
+/// every channel's webhook failed, so that the bot posts the response itself.
+/// Channels whose webhook failed while others worked get the bot's own post
+async fn respond_as(
+    ctx: &Context,
+    msg: &Message,
+    persona: &Persona,
+    response_type: &CommandResponse,
+    value: &str,
+    embeds: Vec<Value>,
+) -> Option<Vec<Message>> {
+    use CommandResponse::*;
+    let channel_ids = match response_type {
+        Channel | Embed => vec![msg.channel_id],
+        Channels(ids) => ids.iter().map(|id| ChannelId(*id)).collect(),
+        Dm | DmOwner | Reply => return None,
+    };
+    let content = content_safe(&ctx.cache, value, &ContentSafeOptions::default()).await;
+
+    let mut sent = Vec::new();
+    let mut failed = Vec::new();
+    for channel_id in channel_ids {
+        let result = match response_type {
+            Embed => persona_embeds(ctx, channel_id, persona, embeds.clone()).await,
+            _ => persona_say(ctx, channel_id, persona, &content).await,
+        };
+        match result {
+            Ok(messages) => sent.extend(messages),
+            Err(e) => {
+                if is_missing_permissions(&e) {
+                    info!("Missing Manage Webhooks permission in {}", channel_id);
+                } else {
+                    info!("Persona announcement in {} failed: {}", channel_id, e);
                 }
+                failed.push(channel_id.0);
             }
-            break;
         }
     }
+    if sent.is_empty() {
+        return None;
+    }
+    if !failed.is_empty() {
+        sent.extend(announce_to_channels(ctx, msg, value, &failed, &[]).await);
+    }
+    Some(sent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_args_follow_prefix_and_name() {
+        assert_eq!(command_args("!so @muffet hi", "!", "so"), " @muffet hi");
+        assert_eq!(command_args("!so", "!", "so"), "");
+    }
+
+    #[test]
+    fn command_args_ignore_name_inside_prefix_or_mention() {
+        assert_eq!(command_args("so!so a b", "so!", "so"), " a b");
+        assert_eq!(command_args("<@123> so a b", "!", "so"), " a b");
+        assert_eq!(command_args("<@!123>so a", "!", "so"), " a");
+        assert_eq!(command_args("hello so a", "!", "so"), "");
+    }
 }
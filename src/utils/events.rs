@@ -0,0 +1,200 @@
+/// SEE https://docs.rs/serenity/0.9.4/serenity/client/trait.EventHandler.html for available events
+use crate::prelude::*;
+use crate::utils::{
+    anti_raid::{is_locked_down, watch_join},
+    config::{render_greeting, GreetingData},
+    link_filter::filter_links,
+    reaction_roles::{check_reaction_roles, reaction_added, reaction_removed},
+    timers::record_activity,
+    word_filter::filter_words,
+};
+use once_cell::sync::Lazy;
+use serenity::{
+    async_trait,
+    model::{
+        channel::Reaction,
+        guild::Member,
+        id::{ChannelId, GuildId},
+        user::User,
+    },
+};
+use std::{collections::HashMap, time::Instant};
+
+pub struct Handler;
+
+/// when each responder, identified by its pattern, last fired in a channel
+static RESPONDER_COOLDOWNS: Lazy<Mutex<HashMap<(String, u64), Instant>>> =
+    Lazy::new(Mutex::default);
+
+#[async_trait]
+impl EventHandler for Handler {
+    /// custom emoji can only be looked up once the guilds are cached
+    async fn cache_ready(&self, ctx: Context, _guilds: Vec<GuildId>) {
+        if let Err(e) = crate::CONFIG
+            .lock()
+            .await
+            .resolve_reactions(&ctx.cache)
+            .await
+        {
+            info!("{}", e);
+        }
+        for problem in check_reaction_roles(&ctx).await {
+            info!("Reaction role problem: {}", problem);
+        }
+    }
+
+    async fn reaction_add(&self, ctx: Context, reaction: Reaction) {
+        reaction_added(&ctx, &reaction).await;
+    }
+
+    async fn reaction_remove(&self, ctx: Context, reaction: Reaction) {
+        reaction_removed(&ctx, &reaction).await;
+    }
+
+    async fn message(&self, ctx: Context, msg: Message) {
+        if msg.author.bot {
+            return;
+        }
+        if filter_links(&ctx, &msg).await || filter_words(&ctx, &msg).await {
+            return;
+        }
+        if msg.guild_id.is_some() {
+            record_activity(msg.channel_id).await;
+        }
+        respond_to_chat(&ctx, &msg).await;
+    }
+
+    async fn guild_member_addition(&self, ctx: Context, guild_id: GuildId, new_member: Member) {
+        watch_join(&ctx, guild_id, &new_member).await;
+        if is_locked_down(guild_id).await {
+            return;
+        }
+        let welcome = match crate::CONFIG.lock().await.get_welcome() {
+            Some(welcome) => welcome.clone(),
+            None => return,
+        };
+        greet(&ctx, guild_id, &new_member.user, &welcome).await;
+
+        if let Some(dm) = welcome.get_dm() {
+            let content = greeting_content(&ctx, guild_id, &new_member.user, dm).await;
+            if let Err(e) = new_member
+                .user
+                .direct_message(&ctx, |m| m.content(content))
+                .await
+            {
+                info!(
+                    "Unable to send welcome dm to {}: {}",
+                    new_member.user.name, e
+                );
+            }
+        }
+    }
+
+    async fn guild_member_removal(
+        &self,
+        ctx: Context,
+        guild_id: GuildId,
+        user: User,
+        _member_data_if_available: Option<Member>,
+    ) {
+        // raiders being kicked would flood the channel otherwise
+        if is_locked_down(guild_id).await {
+            return;
+        }
+        let goodbye = match crate::CONFIG.lock().await.get_goodbye() {
+            Some(goodbye) => goodbye.clone(),
+            None => return,
+        };
+        greet(&ctx, guild_id, &user, &goodbye).await;
+    }
+}
+
+/// fills in a greeting template for `user` joining or leaving the guild
+async fn greeting_content(ctx: &Context, guild_id: GuildId, user: &User, template: &str) -> String {
+    let (server, member_count) = ctx
+        .cache
+        .guild_field(guild_id, |g| (g.name.clone(), g.member_count))
+        .await
+        .unwrap_or_default();
+    render_greeting(template, user, &server, member_count)
+}
+
+/// posts a welcome or goodbye message into its configured channel
+async fn greet(ctx: &Context, guild_id: GuildId, user: &User, greeting: &GreetingData) {
+    let content = greeting_content(ctx, guild_id, user, greeting.get_message()).await;
+    let channel_id = ChannelId(greeting.get_channel());
+
+    let result = if greeting.is_embed() {
+        channel_id
+            .send_message(&ctx.http, |m| {
+                m.embed(|e| {
+                    e.colour(greeting.get_color());
+                    e.description(&content);
+                    e.thumbnail(user.face());
+                    e
+                })
+            })
+            .await
+            .map(|_| ())
+    } else {
+        say_split(&ctx.http, channel_id, &content, &[])
+            .await
+            .map(|_| ())
+    };
+    if let Err(e) = result {
+        info!("Unable to greet {} in {}: {}", user.name, channel_id, e);
+    }
+}
+
+/// runs the first responder matching a chat message that isn't a command
+#[instrument]
+async fn respond_to_chat(ctx: &Context, msg: &Message) {
+    let (responder, auto_delete) = {
+        let config = crate::CONFIG.lock().await;
+        if msg.content.starts_with(config.get_command_prefix()) {
+            return;
+        }
+        match config
+            .get_responders()
+            .iter()
+            .find(|r| r.watches(msg.channel_id.0) && r.matches(&msg.content))
+        {
+            Some(responder) => (responder.clone(), config.get_auto_delete().clone()),
+            None => return,
+        }
+    };
+
+    {
+        let key = (responder.get_pattern().to_string(), msg.channel_id.0);
+        let mut cooldowns = RESPONDER_COOLDOWNS.lock().await;
+        if let Some(last) = cooldowns.get(&key) {
+            if last.elapsed().as_secs() < responder.get_cooldown_secs() {
+                return;
+            }
+        }
+        cooldowns.insert(key, Instant::now());
+    }
+
+    for reaction in responder.get_reactions() {
+        if let Err(e) = msg.react(ctx, reaction.clone()).await {
+            info!("Responder reaction failed: {}", e);
+        }
+    }
+    if responder.get_value().is_empty() {
+        return;
+    }
+    // trigger messages are regular chat, so they are never deleted
+    let auto_delete = auto_delete.without_trigger();
+    if let Err(e) = announce_with_cleanup(
+        ctx,
+        msg,
+        responder.get_value(),
+        responder.get_response_type(),
+        &auto_delete,
+        &[],
+    )
+    .await
+    {
+        info!("Responder announcement failed: {}", e);
+    }
+}
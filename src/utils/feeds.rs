@@ -0,0 +1,219 @@
+use crate::prelude::*;
+use crate::utils::{
+    config::FeedData,
+    store::{load, save},
+    web::fetch,
+};
+use anyhow::Result;
+use serde_derive::{Deserialize, Serialize};
+use serenity::{http::Http, model::id::ChannelId};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// data file remembering which items of each feed were already posted
+const FEED_STATE: &str = "feed_state.toml";
+
+/// how often feeds are checked for being due
+const TICK_SECS: u64 = 60;
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct FeedState {
+    /// ids of the items last seen, keyed by feed url
+    seen: HashMap<String, Vec<String>>,
+}
+
+/// An item of a feed, reduced to what gets posted.
+struct Item {
+    id: String,
+    title: String,
+    link: String,
+    author: String,
+}
+
+/// Posts new items of the configured RSS and Atom feeds. Feeds are read from
+/// `CONFIG` on every tick so that hot reloads apply right away.
+pub async fn run_feeds(http: Arc<Http>) {
+    let mut state: FeedState = load(FEED_STATE).unwrap_or_else(|e| {
+        info!("Unable to read feed state: {}", e);
+        FeedState::default()
+    });
+    let mut last_polls: HashMap<String, Instant> = HashMap::new();
+    let mut ticker = tokio::time::interval(Duration::from_secs(TICK_SECS));
+
+    loop {
+        ticker.tick().await;
+        let feeds = crate::CONFIG.lock().await.get_feeds().clone();
+        let configured = |url: &String| feeds.iter().any(|f| f.get_url() == url);
+        state.seen.retain(|url, _| configured(url));
+        last_polls.retain(|url, _| configured(url));
+
+        let mut changed = false;
+        for feed in &feeds {
+            match last_polls.get(feed.get_url()) {
+                Some(polled) if polled.elapsed() < feed.get_interval() => continue,
+                _ => {}
+            }
+            last_polls.insert(feed.get_url().to_string(), Instant::now());
+
+            match poll(&http, feed, state.seen.get(feed.get_url())).await {
+                Ok(seen) => {
+                    state.seen.insert(feed.get_url().to_string(), seen);
+                    changed = true;
+                }
+                Err(e) => info!("Unable to check feed {}: {}", feed.get_url(), e),
+            }
+        }
+
+        if changed {
+            if let Err(e) = save(FEED_STATE, &state) {
+                info!("Unable to save feed state: {}", e);
+            }
+        }
+    }
+}
+
+/// Fetches a feed and posts the items not in `seen`, oldest first.
+/// Returns the ids to remember for the next check, which leave out the items
+/// that couldn't be posted so they are tried again.
+async fn poll(http: &Http, feed: &FeedData, seen: Option<&Vec<String>>) -> Result<Vec<String>> {
+    let (feed_title, new, mut ids) = new_items(feed, seen).await?;
+    for item in new {
+        let content = feed.render(&feed_title, &item.title, &item.link, &item.author);
+        let mut posted = true;
+        for channel in feed.get_channels() {
+            if let Err(e) = say_split(http, ChannelId(*channel), &content, &[]).await {
+                info!("Unable to post feed item to {}: {}", channel, e);
+                posted = false;
+            }
+        }
+        if !posted {
+            ids.retain(|id| *id != item.id);
+        }
+    }
+    Ok(ids)
+}
+
+/// Fetches a feed, returning its title, the items not in `seen` oldest first,
+/// and the ids to remember. The first time a feed is checked no item is new,
+/// so adding a feed doesn't flood the channel.
+async fn new_items(
+    feed: &FeedData,
+    seen: Option<&Vec<String>>,
+) -> Result<(String, Vec<Item>, Vec<String>)> {
+    let body = fetch(feed.get_url()).await?;
+    let parsed = feed_rs::parser::parse(&body[..])?;
+    let feed_title = parsed.title.map(|t| t.content).unwrap_or_default();
+    let items: Vec<Item> = parsed
+        .entries
+        .into_iter()
+        .map(|entry| Item {
+            title: entry.title.map(|t| t.content).unwrap_or_default(),
+            link: entry
+                .links
+                .into_iter()
+                .next()
+                .map(|l| l.href)
+                .unwrap_or_default(),
+            author: entry
+                .authors
+                .into_iter()
+                .next()
+                .map(|a| a.name)
+                .unwrap_or_default(),
+            id: entry.id,
+        })
+        .collect();
+    // items that dropped off the feed won't come back, so only current ids are kept
+    let ids = items.iter().map(|item| item.id.clone()).collect();
+
+    let new = match seen {
+        // feeds list their newest items first
+        Some(seen) => items
+            .into_iter()
+            .rev()
+            .filter(|item| !seen.contains(&item.id))
+            .collect(),
+        None => vec![],
+    };
+    Ok((feed_title, new, ids))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{config::test_config, fixture::Fixture};
+
+    fn rss(items: &[&str]) -> String {
+        let items: String = items
+            .iter()
+            .map(|id| {
+                format!(
+                    "<item><guid>{0}</guid><title>Video {0}</title><link>https://example.com/{0}</link></item>",
+                    id
+                )
+            })
+            .collect();
+        format!(
+            "<?xml version=\"1.0\"?><rss version=\"2.0\"><channel><title>Muffet</title>{}</channel></rss>",
+            items
+        )
+    }
+
+    async fn feed(url: &str) -> FeedData {
+        test_config(&format!("[[feed]]\nurl = \"{}\"\nchannel = 1", url))
+            .data()
+            .await
+            .unwrap()
+            .get_feeds()[0]
+            .clone()
+    }
+
+    fn titles(items: &[Item]) -> Vec<&str> {
+        items.iter().map(|item| item.title.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn first_check_posts_nothing() {
+        let fixture = Fixture::start();
+        fixture.respond("/feed.xml", &rss(&["2", "1"]));
+        let feed = feed(&fixture.url("/feed.xml")).await;
+
+        let (title, new, ids) = new_items(&feed, None).await.unwrap();
+        assert_eq!(title, "Muffet");
+        assert!(new.is_empty());
+        assert_eq!(ids, vec!["2", "1"]);
+    }
+
+    #[tokio::test]
+    async fn only_unseen_items_are_new_oldest_first() {
+        let fixture = Fixture::start();
+        fixture.respond("/feed.xml", &rss(&["2", "1"]));
+        let feed = feed(&fixture.url("/feed.xml")).await;
+        let (_, _, seen) = new_items(&feed, None).await.unwrap();
+
+        fixture.respond("/feed.xml", &rss(&["4", "3", "2", "1"]));
+        let (_, new, seen) = new_items(&feed, Some(&seen)).await.unwrap();
+        assert_eq!(titles(&new), vec!["Video 3", "Video 4"]);
+        assert_eq!(new[0].link, "https://example.com/3");
+
+        // checking again without changes posts nothing twice
+        let (_, new, seen) = new_items(&feed, Some(&seen)).await.unwrap();
+        assert!(new.is_empty());
+
+        // an item dropping off the feed is forgotten, not posted again
+        fixture.respond("/feed.xml", &rss(&["4", "3"]));
+        let (_, new, seen) = new_items(&feed, Some(&seen)).await.unwrap();
+        assert!(new.is_empty());
+        assert_eq!(seen, vec!["4", "3"]);
+    }
+
+    #[tokio::test]
+    async fn missing_feed_is_an_error() {
+        let fixture = Fixture::start();
+        let feed = feed(&fixture.url("/gone.xml")).await;
+        assert!(new_items(&feed, None).await.is_err());
+    }
+}
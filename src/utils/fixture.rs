@@ -0,0 +1,91 @@
+//! A local HTTP server answering tests with canned responses.
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+    net::TcpListener,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+/// status, extra header lines and body of a canned response
+type Response = (u16, Vec<String>, String);
+
+/// Serves whatever was last set for each path, and 404 for everything else.
+/// The server thread runs until the test binary exits.
+pub struct Fixture {
+    base_url: String,
+    responses: Arc<Mutex<HashMap<String, Response>>>,
+}
+
+impl Fixture {
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("free local port");
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let responses: Arc<Mutex<HashMap<String, Response>>> = Arc::default();
+
+        let served = responses.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = match stream {
+                    Ok(stream) => stream,
+                    Err(_) => continue,
+                };
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match stream.read(&mut buffer) {
+                        Ok(0) | Err(_) => break,
+                        Ok(read) => request.extend_from_slice(&buffer[..read]),
+                    }
+                }
+                let request = String::from_utf8_lossy(&request);
+                let path = request.split_whitespace().nth(1).unwrap_or("/");
+                let (status, headers, body) = served
+                    .lock()
+                    .unwrap()
+                    .get(path)
+                    .cloned()
+                    .unwrap_or((404, vec![], String::from("not found")));
+
+                let mut response = format!(
+                    "HTTP/1.1 {} Fixture\r\nContent-Length: {}\r\nConnection: close\r\n",
+                    status,
+                    body.len()
+                );
+                for header in headers {
+                    response += &format!("{}\r\n", header);
+                }
+                response += "\r\n";
+                response += &body;
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+
+        Self {
+            base_url,
+            responses,
+        }
+    }
+
+    /// Full url of `path` on the server.
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// Answers requests for `path` with `body` from now on.
+    pub fn respond(&self, path: &str, body: &str) {
+        self.respond_with(path, 200, &[], body);
+    }
+
+    /// Answers requests for `path` with the given status and header lines from now on.
+    pub fn respond_with(&self, path: &str, status: u16, headers: &[&str], body: &str) {
+        self.responses.lock().unwrap().insert(
+            path.to_string(),
+            (
+                status,
+                headers.iter().map(|h| h.to_string()).collect(),
+                body.to_string(),
+            ),
+        );
+    }
+}
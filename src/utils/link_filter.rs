@@ -0,0 +1,270 @@
+use crate::prelude::*;
+use crate::utils::config::{FilterAction, LinkFilterData};
+use crate::utils::moderation::{is_exempt, log_filtered, strip_invisible};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// hosts, and the host and path prefix pairs, that are Discord server invites
+const INVITE_HOSTS: &[&str] = &["discord.gg", "discord.io", "discord.me", "dsc.gg"];
+const INVITE_PATHS: &[(&str, &str)] = &[("discord.com", "/invite"), ("discordapp.com", "/invite")];
+
+/// something that looks like a link, with or without a scheme
+static LINK: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)(?P<scheme>[a-z][a-z0-9+.\-]*://)?(?:[^\s/@<>]+@)?(?P<host>(?:[\p{L}\p{N}](?:[\p{L}\p{N}\-]*[\p{L}\p{N}])?\.)+(?:\p{L}{2,}|xn--[a-z0-9\-]+))(?::\d+)?(?P<path>/[^\s<>]*)?",
+    )
+    .expect("valid link pattern")
+});
+
+/// dots written as `[.]`, `(dot)` and the like to dodge link detection
+static OBFUSCATED_DOT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)\s*[\[\(\{]\s*(?:\.|dot)\s*[\]\)\}]\s*").expect("valid dot pattern")
+});
+
+/// percent encoded letters, digits, dots and dashes
+static PERCENT_ENCODED: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"%([0-9A-Fa-f]{2})").expect("valid percent pattern"));
+
+/// Normalizes a configured domain to lowercase punycode, the form links are matched in.
+pub fn filter_domain(domain: &str) -> anyhow::Result<String> {
+    let trimmed = domain.trim().trim_start_matches("*.").trim_end_matches('.');
+    match reqwest::Url::parse(&format!("http://{}", trimmed))
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+    {
+        Some(host) => Ok(host.trim_start_matches("www.").to_string()),
+        None => anyhow::bail!("link_filter: `{}` is not a domain", domain),
+    }
+}
+
+/// A link found in a message
+struct FoundLink {
+    /// lowercase punycode, without `www.`
+    host: String,
+    path: String,
+    /// written with a scheme or `www.`, rather than a bare domain that might be a file name
+    explicit: bool,
+}
+
+/// Undoes the usual tricks for hiding links: invisible characters, look-alike
+/// dots, `[.]`, `hxxp`, backslashes and percent encoding.
+fn deobfuscate(content: &str) -> String {
+    let cleaned: String = strip_invisible(content)
+        .chars()
+        .map(|c| match c {
+            '。' | '．' | '｡' | '․' => '.',
+            '\\' => '/',
+            c => c,
+        })
+        .collect();
+    let cleaned = OBFUSCATED_DOT.replace_all(&cleaned, ".");
+    let cleaned =
+        PERCENT_ENCODED.replace_all(&cleaned, |caps: &regex::Captures| match u8::from_str_radix(
+            &caps[1], 16,
+        ) {
+            Ok(byte) if byte.is_ascii_alphanumeric() || byte == b'.' || byte == b'-' => {
+                (byte as char).to_string()
+            }
+            _ => caps[0].to_string(),
+        });
+    cleaned.replace("hxxp", "http").replace("HXXP", "http")
+}
+
+fn find_links(content: &str) -> Vec<FoundLink> {
+    LINK.captures_iter(&deobfuscate(content))
+        .filter_map(|caps| {
+            let host = &caps["host"];
+            // the url parser turns unicode hosts into punycode
+            let host = reqwest::Url::parse(&format!("http://{}", host))
+                .ok()?
+                .host_str()?
+                .to_string();
+            Some(FoundLink {
+                explicit: caps.name("scheme").is_some() || host.starts_with("www."),
+                host: host.trim_start_matches("www.").to_string(),
+                path: caps
+                    .name("path")
+                    .map_or(String::new(), |path| path.as_str().to_lowercase()),
+            })
+        })
+        .collect()
+}
+
+/// true if `host` is `domain` or one of its subdomains
+fn on_domain(host: &str, domain: &str) -> bool {
+    host == domain || host.ends_with(&format!(".{}", domain))
+}
+
+fn is_invite(link: &FoundLink) -> bool {
+    INVITE_HOSTS.iter().any(|host| on_domain(&link.host, host))
+        || INVITE_PATHS
+            .iter()
+            .any(|(host, path)| on_domain(&link.host, host) && link.path.starts_with(path))
+}
+
+/// Why the filter catches a message, if it does. Bare domains without a
+/// scheme or `www.` are only checked against the deny list, since in allow
+/// list mode every file name would look like a link.
+pub fn violation(filter: &LinkFilterData, content: &str) -> Option<String> {
+    for link in find_links(content) {
+        if filter.blocks_invites() && is_invite(&link) {
+            return Some(String::from("server invites aren't allowed"));
+        }
+        if filter.get_deny().iter().any(|d| on_domain(&link.host, d)) {
+            return Some(format!("links to `{}` aren't allowed", link.host));
+        }
+        if link.explicit
+            && !filter.get_allow().is_empty()
+            && !filter.get_allow().iter().any(|d| on_domain(&link.host, d))
+        {
+            return Some(format!("links to `{}` aren't allowed", link.host));
+        }
+    }
+    None
+}
+
+/// Applies the link filter to a chat message. Returns true if the message was removed.
+#[instrument]
+pub async fn filter_links(ctx: &Context, msg: &Message) -> bool {
+    let guild_id = match msg.guild_id {
+        Some(guild_id) => guild_id,
+        None => return false,
+    };
+    let filter = match crate::CONFIG.lock().await.get_link_filter() {
+        Some(filter) if filter.watches(msg.channel_id.0) => filter.clone(),
+        _ => return false,
+    };
+    let reason = match violation(&filter, &msg.content) {
+        Some(reason) => reason,
+        None => return false,
+    };
+    if is_exempt(ctx, msg, guild_id, filter.get_exempt_roles()).await {
+        return false;
+    }
+
+    let removed = match filter.get_action() {
+        FilterAction::Log => false,
+        FilterAction::Delete | FilterAction::Warn => match msg.delete(ctx).await {
+            Ok(_) => true,
+            Err(e) => {
+                info!("Link filter couldn't delete a message: {}", e);
+                false
+            }
+        },
+    };
+    if removed && *filter.get_action() == FilterAction::Warn {
+        let warning = format!(
+            "{}, your message was removed: {}",
+            msg.author.mention(),
+            reason
+        );
+        if let Err(e) = msg.channel_id.say(&ctx.http, warning).await {
+            info!("Link filter warning failed: {}", e);
+        }
+    }
+    if let Some(log_channel) = filter.get_log_channel() {
+        let action = if removed { "removed" } else { "flagged" };
+        log_filtered(ctx, log_channel, "🔗", action, msg, &reason).await;
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::config::test_config;
+
+    async fn filter(settings: &str) -> LinkFilterData {
+        test_config(&format!("[link_filter]\n{}", settings))
+            .data()
+            .await
+            .unwrap()
+            .get_link_filter()
+            .unwrap()
+            .clone()
+    }
+
+    #[test]
+    fn filter_domains_are_normalized() {
+        assert_eq!(
+            filter_domain(" *.WWW.Example.com. ").unwrap(),
+            "example.com"
+        );
+        assert_eq!(filter_domain("bücher.de").unwrap(), "xn--bcher-kva.de");
+        assert!(filter_domain("not a domain").is_err());
+    }
+
+    fn hosts(content: &str) -> Vec<String> {
+        find_links(content)
+            .into_iter()
+            .map(|link| link.host)
+            .collect()
+    }
+
+    #[test]
+    fn finds_links_with_and_without_schemes() {
+        assert_eq!(
+            hosts("see https://www.YouTube.com/watch?v=1 and twitch.tv/muffet"),
+            vec!["youtube.com", "twitch.tv"]
+        );
+        let links = find_links("file.txt www.site.com http://a.io/X");
+        assert_eq!(
+            links.iter().map(|l| l.explicit).collect::<Vec<bool>>(),
+            vec![false, true, true]
+        );
+        assert_eq!(links[2].path, "/x");
+    }
+
+    #[test]
+    fn undoes_obfuscation() {
+        assert_eq!(hosts("evil[.]com"), vec!["evil.com"]);
+        assert_eq!(hosts("evil (dot) com"), vec!["evil.com"]);
+        assert_eq!(hosts("hxxps://evil.com"), vec!["evil.com"]);
+        assert_eq!(hosts("e\u{200B}vil.c\u{200D}om"), vec!["evil.com"]);
+        assert_eq!(hosts("evil。com"), vec!["evil.com"]);
+        assert_eq!(hosts("http://%65vil.com"), vec!["evil.com"]);
+        assert_eq!(hosts("https:\\\\evil.com"), vec!["evil.com"]);
+    }
+
+    #[test]
+    fn unicode_hosts_become_punycode() {
+        assert_eq!(hosts("https://bücher.de"), vec!["xn--bcher-kva.de"]);
+    }
+
+    #[tokio::test]
+    async fn deny_list_catches_subdomains_and_bare_domains() {
+        let filter = filter("deny = [\"free-nitro.ru\"]").await;
+        assert!(violation(&filter, "claim at gift.free-nitro.ru now").is_some());
+        assert!(violation(&filter, "free-nitro[.]ru").is_some());
+        assert!(violation(&filter, "not-free-nitro.ru").is_none());
+        assert!(violation(&filter, "nothing here").is_none());
+    }
+
+    #[tokio::test]
+    async fn allow_list_only_checks_explicit_links() {
+        let filter = filter("allow = [\"youtube.com\"]").await;
+        assert!(violation(&filter, "https://m.youtube.com/watch").is_none());
+        assert!(violation(&filter, "https://evil.com").is_some());
+        assert!(violation(&filter, "www.evil.com").is_some());
+        assert!(violation(&filter, "open notes.txt").is_none());
+    }
+
+    #[tokio::test]
+    async fn invites_are_blocked() {
+        let filter = filter("block_invites = true").await;
+        for invite in &[
+            "discord.gg/abc",
+            "https://discord.com/invite/abc",
+            "discord[.]gg/abc",
+            "https://DISCORD.GG/abc",
+        ] {
+            assert_eq!(
+                violation(&filter, invite).as_deref(),
+                Some("server invites aren't allowed"),
+                "{}",
+                invite
+            );
+        }
+        assert!(violation(&filter, "https://discord.com/channels/1/2").is_none());
+    }
+}
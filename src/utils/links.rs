@@ -0,0 +1,147 @@
+use crate::prelude::*;
+use crate::utils::store::{load, save};
+use crate::utils::{
+    config::CommandData,
+    web::{check_link, find_links, LinkStatus},
+};
+use serde_derive::{Deserialize, Serialize};
+use serenity::http::Http;
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+/// data file remembering the links the owner was already told are broken
+const LINK_CHECK_STATE: &str = "link_check_state.toml";
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct LinkCheckState {
+    broken: Vec<String>,
+}
+
+/// A link found in a config command and how requesting it went
+pub struct LinkReport {
+    trigger: String,
+    url: String,
+    status: LinkStatus,
+}
+
+impl LinkReport {
+    pub fn is_broken(&self) -> bool {
+        self.status.is_broken()
+    }
+}
+
+/// Every link in the commands' targets and role variants, including the
+/// `site_url` + `path` ones. Links with argument placeholders are skipped,
+/// since they only exist once someone fills them in.
+fn command_links(commands: &[CommandData]) -> Vec<(String, String)> {
+    let mut links = Vec::new();
+    for cmd in commands {
+        for target in cmd.get_targets() {
+            for url in find_links(target) {
+                let link = (cmd.get_trigger().to_string(), url);
+                if !link.1.contains('{') && !links.contains(&link) {
+                    links.push(link);
+                }
+            }
+        }
+    }
+    links
+}
+
+/// Requests every command link, one after another.
+pub async fn check_links(commands: &[CommandData]) -> Vec<LinkReport> {
+    let mut reports = Vec::new();
+    for (trigger, url) in command_links(commands) {
+        let status = check_link(&url).await;
+        reports.push(LinkReport {
+            trigger,
+            url,
+            status,
+        });
+    }
+    reports
+}
+
+/// Lists the links that redirect or are broken, or says they're all fine.
+pub fn format_reports(reports: &[LinkReport]) -> String {
+    let problems = reports
+        .iter()
+        .filter(|r| r.status != LinkStatus::Ok)
+        .map(|r| format!("> `!{}` {} {}", r.trigger, r.url, r.status))
+        .collect::<Vec<String>>();
+    if problems.is_empty() {
+        format!("All {} links are working!", reports.len())
+    } else {
+        format!(
+            "**Checked {} links, {} need a look**\n{}",
+            reports.len(),
+            problems.len(),
+            problems.join("\n")
+        )
+    }
+}
+
+/// Checks the command links every `link_check_hours` and DMs the owner about
+/// links that broke since the last check. Does nothing while the setting is off.
+pub async fn run_link_checks(http: Arc<Http>) {
+    // loaded so a restart doesn't report the same broken links again
+    let state: LinkCheckState = load(LINK_CHECK_STATE).unwrap_or_else(|e| {
+        info!("Unable to read link check state: {}", e);
+        LinkCheckState::default()
+    });
+    let mut broken: HashSet<String> = state.broken.into_iter().collect();
+    loop {
+        let (hours, commands) = {
+            let config = crate::CONFIG.lock().await;
+            (config.get_link_check_hours(), config.get_commands().clone())
+        };
+        let hours = match hours {
+            Some(hours) => hours,
+            None => {
+                // checked again in a while in case it was turned on by a reload
+                tokio::time::delay_for(Duration::from_secs(60 * 60)).await;
+                continue;
+            }
+        };
+
+        let reports = check_links(&commands).await;
+        let newly_broken: Vec<&LinkReport> = reports
+            .iter()
+            .filter(|r| r.status.is_broken() && !broken.contains(&r.url))
+            .collect();
+        if !newly_broken.is_empty() {
+            let content = format!(
+                "**Some command links just broke**\n{}",
+                newly_broken
+                    .iter()
+                    .map(|r| format!("> `!{}` {} {}", r.trigger, r.url, r.status))
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            );
+            let owner = crate::OWNER.lock().await.clone();
+            match owner.create_dm_channel(&http).await {
+                Ok(dm) => {
+                    if let Err(e) = say_split(&http, dm.id, &content, &[]).await {
+                        info!("Unable to dm broken links: {}", e);
+                    }
+                }
+                Err(e) => info!("Unable to dm broken links: {}", e),
+            }
+        }
+        let now_broken: HashSet<String> = reports
+            .into_iter()
+            .filter(|r| r.status.is_broken())
+            .map(|r| r.url)
+            .collect();
+        if now_broken != broken {
+            broken = now_broken;
+            let state = LinkCheckState {
+                broken: broken.iter().cloned().collect(),
+            };
+            if let Err(e) = save(LINK_CHECK_STATE, &state) {
+                info!("Unable to save link check state: {}", e);
+            }
+        }
+
+        tokio::time::delay_for(Duration::from_secs(hours * 60 * 60)).await;
+    }
+}
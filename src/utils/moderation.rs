@@ -0,0 +1,174 @@
+use crate::prelude::*;
+use crate::utils::store::Store;
+use chrono::Utc;
+use serde_derive::{Deserialize, Serialize};
+use serenity::{
+    http::Http,
+    model::id::{ChannelId, GuildId, RoleId, UserId},
+    utils::{content_safe, ContentSafeOptions},
+};
+use std::sync::Arc;
+
+/// data file holding the timeout roles that still have to be taken away
+const TIMEOUTS_FILE: &str = "timeouts.toml";
+
+/// how often expired timeouts are looked for
+const TICK_SECS: u64 = 15;
+
+/// Drops the invisible characters used to split up words and links.
+pub fn strip_invisible(content: &str) -> String {
+    content
+        .chars()
+        .filter(|c| {
+            !matches!(
+                c,
+                '\u{200B}'..='\u{200D}' | '\u{2060}' | '\u{FEFF}' | '\u{00AD}'
+            )
+        })
+        .collect()
+}
+
+/// true if the author of `msg` isn't moderated by a filter: the bot owner
+/// and members with one of `exempt_roles`, given as names or ids
+pub async fn is_exempt(
+    ctx: &Context,
+    msg: &Message,
+    guild_id: GuildId,
+    exempt_roles: &[String],
+) -> bool {
+    if has_permissions(msg).await {
+        return true;
+    }
+    if exempt_roles.is_empty() {
+        return false;
+    }
+    let roles = member_roles(ctx, guild_id, msg.author.id).await;
+    exempt_roles
+        .iter()
+        .any(|exempt| roles.iter().any(|role| role.eq_ignore_ascii_case(exempt)))
+}
+
+/// true if the author of `msg` can use the moderator commands: the bot owner,
+/// and in a guild, members with one of `moderator_roles`, given as names or ids
+pub async fn is_moderator(ctx: &Context, msg: &Message, moderator_roles: &[String]) -> bool {
+    match msg.guild_id {
+        Some(guild_id) => is_exempt(ctx, msg, guild_id, moderator_roles).await,
+        None => has_permissions(msg).await,
+    }
+}
+
+/// reports a message a filter caught in the log channel
+pub async fn log_filtered(
+    ctx: &Context,
+    log_channel: u64,
+    icon: &str,
+    action: &str,
+    msg: &Message,
+    reason: &str,
+) {
+    let report = format!(
+        "{} {} a message by {} in <#{}>: {}\n>>> {}",
+        icon,
+        action,
+        msg.author.tag(),
+        msg.channel_id,
+        reason,
+        content_safe(&ctx.cache, &msg.content, &ContentSafeOptions::default()).await
+    );
+    if let Err(e) = say_split(&ctx.http, ChannelId(log_channel), &report, &[]).await {
+        info!("Moderation log failed: {}", e);
+    }
+}
+
+/// Looks up a guild role by its id or, ignoring case, its name.
+pub async fn find_role(ctx: &Context, guild_id: GuildId, name_or_id: &str) -> Option<RoleId> {
+    let roles = match ctx.cache.guild_field(guild_id, |g| g.roles.clone()).await {
+        Some(roles) => roles.into_values().collect(),
+        None => ctx
+            .http
+            .get_guild_roles(guild_id.0)
+            .await
+            .unwrap_or_default(),
+    };
+    roles
+        .into_iter()
+        .find(|role| {
+            role.id.0.to_string() == name_or_id || role.name.eq_ignore_ascii_case(name_or_id)
+        })
+        .map(|role| role.id)
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct Timeout {
+    guild_id: u64,
+    user_id: u64,
+    role_id: u64,
+    /// unix timestamp
+    until: i64,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct TimeoutList {
+    timeouts: Vec<Timeout>,
+}
+
+static TIMEOUTS: Store<TimeoutList> = Store::new(TIMEOUTS_FILE);
+
+/// Gives a member the timeout role for `duration`, extending a timeout they
+/// are already serving. The role is taken away again by `run_timeouts`,
+/// which also catches up on timeouts that ran out while the bot was offline.
+pub async fn time_out(
+    http: &Http,
+    guild_id: GuildId,
+    user_id: UserId,
+    role_id: RoleId,
+    duration: std::time::Duration,
+) -> serenity::Result<()> {
+    http.add_member_role(guild_id.0, user_id.0, role_id.0)
+        .await?;
+
+    let until = Utc::now().timestamp() + duration.as_secs() as i64;
+    let mut list = TIMEOUTS.lock().await;
+    list.timeouts.retain(|t| {
+        !(t.guild_id == guild_id.0 && t.user_id == user_id.0 && t.role_id == role_id.0)
+    });
+    list.timeouts.push(Timeout {
+        guild_id: guild_id.0,
+        user_id: user_id.0,
+        role_id: role_id.0,
+        until,
+    });
+    TIMEOUTS.persist(&list);
+    Ok(())
+}
+
+/// Takes timeout roles away again once they run out.
+pub async fn run_timeouts(http: Arc<Http>) {
+    let mut ticker = tokio::time::interval(std::time::Duration::from_secs(TICK_SECS));
+
+    loop {
+        ticker.tick().await;
+        let now = Utc::now().timestamp();
+        let expired: Vec<Timeout> = {
+            let mut list = TIMEOUTS.lock().await;
+            let (expired, serving) = list.timeouts.drain(..).partition(|t| t.until <= now);
+            list.timeouts = serving;
+            if !expired.is_empty() {
+                TIMEOUTS.persist(&list);
+            }
+            expired
+        };
+
+        for timeout in expired {
+            if let Err(e) = http
+                .remove_member_role(timeout.guild_id, timeout.user_id, timeout.role_id)
+                .await
+            {
+                // members who left in the meantime can't be found anymore
+                if !is_not_found(&e) {
+                    info!("Unable to end timeout of {}: {}", timeout.user_id, e);
+                }
+            }
+        }
+    }
+}
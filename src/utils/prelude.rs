@@ -1,16 +1,106 @@
-pub use crate::utils::config::CommandResponse;
-use serenity::utils::{content_safe, ContentSafeOptions};
+pub use crate::utils::config::{AutoDelete, CommandResponse};
+use crate::utils::split::{split_embed, split_message, MESSAGE_LIMIT};
+use serenity::{
+    builder::CreateEmbed,
+    http::Http,
+    model::id::{ChannelId, GuildId, UserId},
+    utils::{content_safe, ContentSafeOptions},
+};
 pub use serenity::{
     framework::standard::{macros::command, Args, CommandResult},
     model::channel::Message,
     prelude::*,
 };
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 pub use tracing::{info, instrument};
 
 pub async fn has_permissions(msg: &Message) -> bool {
     msg.author == *crate::OWNER.lock().await
 }
 
+/// ids and names of a member's roles, looked up in the cache first and over HTTP otherwise
+pub async fn member_roles(ctx: &Context, guild_id: GuildId, user_id: UserId) -> Vec<String> {
+    let role_ids = match guild_id.member(ctx, user_id).await {
+        Ok(member) => member.roles,
+        Err(e) => {
+            info!("Unable to look up member {}: {}", user_id, e);
+            return vec![];
+        }
+    };
+    let guild_roles = match ctx.cache.guild_field(guild_id, |g| g.roles.clone()).await {
+        Some(roles) => roles.into_values().collect(),
+        None => ctx
+            .http
+            .get_guild_roles(guild_id.0)
+            .await
+            .unwrap_or_default(),
+    };
+
+    let mut roles = Vec::new();
+    for role_id in role_ids {
+        roles.push(role_id.0.to_string());
+        if let Some(role) = guild_roles.iter().find(|role| role.id == role_id) {
+            roles.push(role.name.clone());
+        }
+    }
+    roles
+}
+
+/// true if Discord refused a request because the bot lacks a permission
+pub fn is_missing_permissions(e: &serenity::Error) -> bool {
+    use serenity::model::ModelError;
+    match e {
+        serenity::Error::Model(ModelError::InvalidPermissions(_)) => true,
+        serenity::Error::Http(http_err) => {
+            matches!(http_err.status_code(), Some(status) if status.as_u16() == 403)
+        }
+        _ => false,
+    }
+}
+
+/// true if Discord couldn't find the requested resource
+pub fn is_not_found(e: &serenity::Error) -> bool {
+    match e {
+        serenity::Error::Http(http_err) => {
+            matches!(http_err.status_code(), Some(status) if status.as_u16() == 404)
+        }
+        _ => false,
+    }
+}
+
+/// set once the missing Manage Messages permission has been logged
+static MANAGE_MESSAGES_LOGGED: AtomicBool = AtomicBool::new(false);
+
+/// deletes the triggering message and schedules deletion of the bot's response
+/// according to `auto_delete`
+pub async fn clean_up(ctx: &Context, msg: &Message, sent: Vec<Message>, auto_delete: &AutoDelete) {
+    if auto_delete.deletes_trigger() && msg.guild_id.is_some() {
+        if let Err(e) = msg.delete(ctx).await {
+            if !is_missing_permissions(&e) {
+                info!("Unable to delete trigger message: {}", e);
+            } else if !MANAGE_MESSAGES_LOGGED.swap(true, Ordering::Relaxed) {
+                info!("Missing Manage Messages permission, trigger messages will not be deleted");
+            }
+        }
+    }
+
+    if let Some(secs) = auto_delete.get_after_secs() {
+        if sent.is_empty() {
+            return;
+        }
+        let ctx = ctx.clone();
+        tokio::spawn(async move {
+            tokio::time::delay_for(std::time::Duration::from_secs(secs)).await;
+            for sent in sent {
+                if let Err(e) = sent.delete(&ctx).await {
+                    info!("Unable to delete response: {}", e);
+                }
+            }
+        });
+    }
+}
+
 /// announces given message to entire thread. `announcement` can be any type that implements as_ref for string slice
 pub async fn announce<S>(
     ctx: &Context,
@@ -18,69 +108,199 @@ pub async fn announce<S>(
     announcement: S,
     response_type: &CommandResponse,
 ) -> CommandResult
+where
+    S: std::fmt::Display + std::fmt::Debug + AsRef<str>,
+{
+    let auto_delete = crate::CONFIG.lock().await.get_auto_delete().clone();
+    announce_with_cleanup(ctx, msg, announcement, response_type, &auto_delete, &[]).await
+}
+
+/// same as `announce`, but with explicit auto delete settings instead of the global ones
+/// which `announce` has to lock `CONFIG` for. `files` are uploaded along with the announcement
+pub async fn announce_with_cleanup<S>(
+    ctx: &Context,
+    msg: &Message,
+    announcement: S,
+    response_type: &CommandResponse,
+    auto_delete: &AutoDelete,
+    files: &[PathBuf],
+) -> CommandResult
 where
     S: std::fmt::Display + std::fmt::Debug + AsRef<str>,
 {
     use CommandResponse::*;
-    match response_type {
-        Dm => direct_message(ctx, msg, announcement, false).await,
-        DmOwner => direct_message(ctx, msg, announcement, true).await,
-        Reply => reply_to_sender(ctx, msg, announcement).await,
-        Channel => announce_to_channel(ctx, msg, announcement).await,
-        _ => Ok(()),
+    let sent = match response_type {
+        Dm => direct_message(ctx, msg, announcement, false, files).await,
+        DmOwner => direct_message(ctx, msg, announcement, true, files).await,
+        Reply => reply_to_sender(ctx, msg, announcement, files).await,
+        Channel => announce_to_channel(ctx, msg, announcement, files).await,
+        Channels(ids) => announce_to_channels(ctx, msg, announcement, ids, files).await,
+        _ => vec![],
+    };
+    clean_up(ctx, msg, sent, auto_delete).await;
+    Ok(())
+}
+
+/// sends `content` to the channel, split into as many messages as needed.
+/// `files` are uploaded with the first message
+pub async fn say_split(
+    http: &Http,
+    channel_id: ChannelId,
+    content: &str,
+    files: &[PathBuf],
+) -> serenity::Result<Vec<Message>> {
+    let mut sent = Vec::new();
+    for (i, piece) in split_message(content, MESSAGE_LIMIT).iter().enumerate() {
+        let files = if i == 0 { files } else { &[] };
+        sent.push(
+            channel_id
+                .send_message(http, |m| m.content(piece).add_files(files))
+                .await?,
+        );
+    }
+    Ok(sent)
+}
+
+/// fills `embed` with one page of `split_embed` output.
+/// Only the very first field is titled with `name`, the rest continue it
+pub fn embed_page(embed: &mut CreateEmbed, name: &str, page: &[String], first_page: bool) {
+    for (i, value) in page.iter().enumerate() {
+        let field_name = if first_page && i == 0 {
+            name
+        } else {
+            "\u{200b}"
+        };
+        embed.field(field_name, value, false);
     }
 }
 
 /// announces given message to entire thread. `announcement` can be any type that implements as_ref for string slice
 #[instrument]
-async fn announce_to_channel<S>(ctx: &Context, msg: &Message, announcement: S) -> CommandResult
+async fn announce_to_channel<S>(
+    ctx: &Context,
+    msg: &Message,
+    announcement: S,
+    files: &[PathBuf],
+) -> Vec<Message>
 where
     S: AsRef<str> + std::fmt::Debug,
 {
     let content = content_safe(&ctx.cache, announcement, &ContentSafeOptions::default()).await;
 
-    if let Err(e) = msg.channel_id.say(&ctx.http, &content).await {
-        info!("Announce error: {:#?}", e);
+    match say_split(&ctx.http, msg.channel_id, &content, files).await {
+        Ok(sent) => sent,
+        Err(e) => {
+            info!("Announce error: {:#?}", e);
+            vec![]
+        }
     }
-    Ok(())
+}
+
+/// announces given message to each of the given channels,
+/// telling the sender about channels that are missing or off limits
+#[instrument]
+pub async fn announce_to_channels<S>(
+    ctx: &Context,
+    msg: &Message,
+    announcement: S,
+    channel_ids: &[u64],
+    files: &[PathBuf],
+) -> Vec<Message>
+where
+    S: AsRef<str> + std::fmt::Debug,
+{
+    let content = content_safe(&ctx.cache, announcement, &ContentSafeOptions::default()).await;
+
+    let mut sent = Vec::new();
+    for id in channel_ids {
+        match say_split(&ctx.http, ChannelId(*id), &content, files).await {
+            Ok(messages) => sent.extend(messages),
+            Err(e) => {
+                info!("Announce error in channel {}: {:#?}", id, e);
+                let problem = if is_missing_permissions(&e) {
+                    format!("I'm not allowed to post in <#{}>", id)
+                } else if is_not_found(&e) {
+                    format!("I couldn't find the channel with id `{}`", id)
+                } else {
+                    format!("I couldn't post in <#{}>: {}", id, e)
+                };
+                if let Err(e) = msg.reply(ctx, problem).await {
+                    info!("Error replying to {}: {}", &msg.author, e.to_string());
+                }
+            }
+        }
+    }
+    sent
 }
 
 /// bot sends dm to admin to avoid leaking info to channel
 #[instrument]
-async fn direct_message<S>(ctx: &Context, msg: &Message, dm: S, to_owner: bool) -> CommandResult
+async fn direct_message<S>(
+    ctx: &Context,
+    msg: &Message,
+    dm: S,
+    to_owner: bool,
+    files: &[PathBuf],
+) -> Vec<Message>
 where
     S: std::fmt::Display + std::fmt::Debug,
 {
     if to_owner && !has_permissions(msg).await {
-        return Ok(());
+        return vec![];
     }
 
+    // sent through the dm channel, since `User::direct_message` can't borrow `files`
+    let dm_channel = match msg.author.create_dm_channel(ctx).await {
+        Ok(channel) => channel,
+        Err(e) => {
+            info!("DM to Admin failed: {}", e.to_string());
+            return vec![];
+        }
+    };
     let color = crate::CONFIG.lock().await.get_help_color().clone();
-    if let Err(e) = msg
-        .author
-        .direct_message(ctx, |m| {
-            m.embed(|embed| {
-                embed.color(color);
-                embed.field("hi!", dm, true);
-                embed
-            });
-            m
-        })
-        .await
-    {
-        info!("DM to Admin failed: {}", e.to_string());
+    let mut sent = Vec::new();
+    for (i, page) in split_embed(&dm.to_string()).iter().enumerate() {
+        let files = if i == 0 { files } else { &[] };
+        match dm_channel
+            .id
+            .send_message(&ctx.http, |m| {
+                m.embed(|embed| {
+                    embed.color(color.clone());
+                    embed_page(embed, "hi!", page, i == 0);
+                    embed
+                });
+                m.add_files(files)
+            })
+            .await
+        {
+            Ok(message) => sent.push(message),
+            Err(e) => {
+                info!("DM to Admin failed: {}", e.to_string());
+                break;
+            }
+        }
     }
-    Ok(())
+    sent
 }
 
 /// bot replies to message sender in channel
 #[instrument]
-async fn reply_to_sender<S>(ctx: &Context, msg: &Message, reply: S) -> CommandResult
+async fn reply_to_sender<S>(
+    ctx: &Context,
+    msg: &Message,
+    reply: S,
+    files: &[PathBuf],
+) -> Vec<Message>
 where
     S: std::fmt::Display + std::fmt::Debug,
 {
-    if let Err(e) = msg.reply(ctx, reply).await {
-        info!("Error replying to {}: {}", &msg.author, e.to_string());
+    let content = format!("{} {}", msg.author.mention(), reply);
+
+    match say_split(&ctx.http, msg.channel_id, &content, files).await {
+        Ok(sent) => sent,
+        Err(e) => {
+            info!("Error replying to {}: {}", &msg.author, e.to_string());
+            vec![]
+        }
     }
-    Ok(())
 }
@@ -0,0 +1,173 @@
+use crate::prelude::*;
+use crate::utils::config::{ReactionRoleData, ReactionRoleMode};
+use crate::utils::moderation::find_role;
+use serenity::model::{
+    channel::Reaction,
+    id::{GuildId, UserId},
+};
+
+/// the bindings of the message a reaction is on, and the one matching its emoji
+async fn bindings_for(reaction: &Reaction) -> Option<(Vec<ReactionRoleData>, ReactionRoleData)> {
+    let bindings: Vec<ReactionRoleData> = crate::CONFIG
+        .lock()
+        .await
+        .get_reaction_roles()
+        .iter()
+        .filter(|binding| binding.get_message_id() == reaction.message_id.0)
+        .cloned()
+        .collect();
+    let binding = bindings
+        .iter()
+        .find(|binding| binding.matches(&reaction.emoji))?
+        .clone();
+    Some((bindings, binding))
+}
+
+/// the guild and member a reaction is from, unless it's the bot's own
+async fn reacting_member(ctx: &Context, reaction: &Reaction) -> Option<(GuildId, UserId)> {
+    let user_id = reaction.user_id?;
+    if user_id == ctx.cache.current_user_id().await {
+        return None;
+    }
+    Some((reaction.guild_id?, user_id))
+}
+
+/// Gives the role bound to a reaction, taking away the message's other roles
+/// and reactions in unique mode.
+#[instrument]
+pub async fn reaction_added(ctx: &Context, reaction: &Reaction) {
+    let (guild_id, user_id) = match reacting_member(ctx, reaction).await {
+        Some(member) => member,
+        None => return,
+    };
+    let (bindings, binding) = match bindings_for(reaction).await {
+        Some(found) => found,
+        None => return,
+    };
+    let role_id = match find_role(ctx, guild_id, binding.get_role()).await {
+        Some(role_id) => role_id,
+        None => {
+            info!("Reaction role `{}` doesn't exist", binding.get_role());
+            return;
+        }
+    };
+    if let Err(e) = ctx
+        .http
+        .add_member_role(guild_id.0, user_id.0, role_id.0)
+        .await
+    {
+        info!("Unable to give reaction role {}: {}", binding.get_role(), e);
+        return;
+    }
+
+    if binding.get_mode() != ReactionRoleMode::Unique {
+        return;
+    }
+    for other in bindings.iter().filter(|b| {
+        b.get_mode() == ReactionRoleMode::Unique && b.get_emoji() != binding.get_emoji()
+    }) {
+        let other_role = find_role(ctx, guild_id, other.get_role()).await;
+        // removing its reaction would take away the role that was just given
+        if other_role == Some(role_id) {
+            continue;
+        }
+        if let Some(other_role) = other_role {
+            if let Err(e) = ctx
+                .http
+                .remove_member_role(guild_id.0, user_id.0, other_role.0)
+                .await
+            {
+                info!("Unable to take reaction role {}: {}", other.get_role(), e);
+            }
+        }
+        // so the message shows the member's one choice
+        if let Some(other_reaction) = other.get_reaction() {
+            if let Err(e) = ctx
+                .http
+                .delete_reaction(
+                    reaction.channel_id.0,
+                    reaction.message_id.0,
+                    Some(user_id.0),
+                    other_reaction,
+                )
+                .await
+            {
+                info!("Unable to remove reaction {}: {}", other.get_emoji(), e);
+            }
+        }
+    }
+}
+
+/// Takes the role bound to a reaction away again, unless it's verify only.
+#[instrument]
+pub async fn reaction_removed(ctx: &Context, reaction: &Reaction) {
+    let (guild_id, user_id) = match reacting_member(ctx, reaction).await {
+        Some(member) => member,
+        None => return,
+    };
+    let binding = match bindings_for(reaction).await {
+        Some((_, binding)) if binding.get_mode() != ReactionRoleMode::Verify => binding,
+        _ => return,
+    };
+    if let Some(role_id) = find_role(ctx, guild_id, binding.get_role()).await {
+        if let Err(e) = ctx
+            .http
+            .remove_member_role(guild_id.0, user_id.0, role_id.0)
+            .await
+        {
+            info!("Unable to take reaction role {}: {}", binding.get_role(), e);
+        }
+    }
+}
+
+/// Checks that every reaction role's message and role exist, and reacts with
+/// its emoji so members only have to click it. Returns the problems found.
+#[instrument]
+pub async fn check_reaction_roles(ctx: &Context) -> Vec<String> {
+    let bindings = crate::CONFIG.lock().await.get_reaction_roles().to_vec();
+
+    let mut problems = Vec::new();
+    for binding in bindings {
+        let (channel_id, message_id) = (binding.get_channel_id(), binding.get_message_id());
+        if let Err(e) = ctx.http.get_message(channel_id, message_id).await {
+            problems.push(format!(
+                "the message {} can't be read: {}",
+                binding.get_link(),
+                e
+            ));
+            continue;
+        }
+        if find_role(ctx, GuildId(binding.get_guild_id()), binding.get_role())
+            .await
+            .is_none()
+        {
+            problems.push(format!(
+                "the role `{}` for {} doesn't exist",
+                binding.get_role(),
+                binding.get_link()
+            ));
+        }
+        match binding.get_reaction() {
+            Some(reaction) => {
+                if let Err(e) = ctx
+                    .http
+                    .create_reaction(channel_id, message_id, reaction)
+                    .await
+                {
+                    problems.push(format!(
+                        "I couldn't react with {} on {}: {}",
+                        binding.get_emoji(),
+                        binding.get_link(),
+                        e
+                    ));
+                }
+            }
+            None => problems.push(format!(
+                "the emoji `{}` for {} wasn't found",
+                binding.get_emoji(),
+                binding.get_link()
+            )),
+        }
+    }
+    problems
+}
@@ -0,0 +1,351 @@
+use crate::prelude::*;
+use crate::utils::store::Store;
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde_derive::{Deserialize, Serialize};
+use serenity::{
+    http::Http,
+    model::id::{ChannelId, UserId},
+};
+use std::sync::Arc;
+
+/// data file holding the reminders that haven't been delivered yet
+const REMINDERS_FILE: &str = "reminders.toml";
+
+/// how often due reminders are looked for
+const TICK_SECS: u64 = 15;
+
+/// reminders more overdue than this were missed while the bot was offline
+const LATE_AFTER_SECS: i64 = 2 * TICK_SECS as i64;
+
+/// pending reminders a single user may have
+const MAX_REMINDERS_PER_USER: usize = 25;
+
+/// furthest ahead a reminder can be set
+const MAX_REMINDER_DAYS: i64 = 366;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Reminder {
+    id: u64,
+    user_id: u64,
+    channel_id: u64,
+    /// unix timestamps
+    created: i64,
+    due: i64,
+    text: String,
+}
+
+impl Reminder {
+    pub fn get_id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn get_due(&self) -> i64 {
+        self.due
+    }
+
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct ReminderList {
+    next_id: u64,
+    reminders: Vec<Reminder>,
+}
+
+static REMINDERS: Store<ReminderList> = Store::new(REMINDERS_FILE);
+
+/// Reads when a reminder is due from the start of `args`, returning the time
+/// and how many words it took up. Understands durations like `90s`, `10m`,
+/// `1h30m`, `2d` or `1w`, a time of day like `18:00`, and a date and time
+/// like `2021-01-31 18:00`, both read in `timezone`.
+pub fn parse_when(
+    args: &[&str],
+    timezone: Tz,
+    now: DateTime<Utc>,
+) -> Option<(DateTime<Utc>, usize)> {
+    let first = args.first()?;
+    if let Some(duration) = parse_duration(first) {
+        return Some((now.checked_add_signed(duration)?, 1));
+    }
+
+    if let (Ok(date), Some(time)) = (
+        NaiveDate::parse_from_str(first, "%Y-%m-%d"),
+        args.get(1)
+            .and_then(|t| NaiveTime::parse_from_str(t, "%H:%M").ok()),
+    ) {
+        let due = timezone
+            .from_local_datetime(&date.and_time(time))
+            .earliest()?;
+        return Some((due.with_timezone(&Utc), 2));
+    }
+
+    let time = NaiveTime::parse_from_str(first, "%H:%M").ok()?;
+    let today = now.with_timezone(&timezone).naive_local().date();
+    let mut due = local_to_utc(timezone, today.and_time(time))?;
+    // a time of day that already passed means tomorrow
+    if due <= now {
+        due = local_to_utc(timezone, today.succ_opt()?.and_time(time))?;
+    }
+    Some((due, 1))
+}
+
+fn local_to_utc(timezone: Tz, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+    timezone
+        .from_local_datetime(&local)
+        .earliest()
+        .map(|due| due.with_timezone(&Utc))
+}
+
+/// `1h30m` style durations, with at least one number and unit pair.
+/// Durations past `MAX_REMINDER_DAYS` are refused before they can overflow
+fn parse_duration(word: &str) -> Option<Duration> {
+    let max_secs = MAX_REMINDER_DAYS * 24 * 60 * 60;
+    let mut total_secs: i64 = 0;
+    let mut number = String::new();
+    for c in word.to_lowercase().chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let amount: i64 = number.parse().ok()?;
+        number.clear();
+        let unit_secs = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            'w' => 7 * 24 * 60 * 60,
+            _ => return None,
+        };
+        total_secs = amount
+            .checked_mul(unit_secs)
+            .and_then(|secs| total_secs.checked_add(secs))
+            .filter(|secs| *secs <= max_secs)?;
+    }
+    if !number.is_empty() || total_secs <= 0 {
+        return None;
+    }
+    Duration::try_seconds(total_secs)
+}
+
+/// Stores a new reminder, returning its id or why it was refused.
+pub async fn add_reminder(
+    user_id: UserId,
+    channel_id: ChannelId,
+    due: DateTime<Utc>,
+    text: String,
+) -> Result<u64, String> {
+    let now = Utc::now();
+    if due <= now {
+        return Err(String::from("That time has already passed!"));
+    }
+    if due > now + Duration::days(MAX_REMINDER_DAYS) {
+        return Err(format!(
+            "Reminders can be set up to {} days ahead.",
+            MAX_REMINDER_DAYS
+        ));
+    }
+
+    let mut list = REMINDERS.lock().await;
+    let pending = list
+        .reminders
+        .iter()
+        .filter(|r| r.user_id == user_id.0)
+        .count();
+    if pending >= MAX_REMINDERS_PER_USER {
+        return Err(format!(
+            "You already have {} reminders, cancel one first.",
+            MAX_REMINDERS_PER_USER
+        ));
+    }
+
+    list.next_id += 1;
+    let id = list.next_id;
+    list.reminders.push(Reminder {
+        id,
+        user_id: user_id.0,
+        channel_id: channel_id.0,
+        created: now.timestamp(),
+        due: due.timestamp(),
+        text,
+    });
+    REMINDERS.persist(&list);
+    Ok(id)
+}
+
+/// A user's pending reminders, soonest first.
+pub async fn user_reminders(user_id: UserId) -> Vec<Reminder> {
+    let mut reminders: Vec<Reminder> = REMINDERS
+        .lock()
+        .await
+        .reminders
+        .iter()
+        .filter(|r| r.user_id == user_id.0)
+        .cloned()
+        .collect();
+    reminders.sort_by_key(|r| r.due);
+    reminders
+}
+
+/// Cancels one of the user's reminders, returning whether it existed.
+pub async fn cancel_reminder(user_id: UserId, id: u64) -> bool {
+    let mut list = REMINDERS.lock().await;
+    let before = list.reminders.len();
+    list.reminders
+        .retain(|r| !(r.id == id && r.user_id == user_id.0));
+    let removed = list.reminders.len() < before;
+    if removed {
+        REMINDERS.persist(&list);
+    }
+    removed
+}
+
+/// Delivers reminders as they fall due, including the ones that fell due
+/// while the bot was offline.
+pub async fn run_reminders(http: Arc<Http>) {
+    let mut ticker = tokio::time::interval(std::time::Duration::from_secs(TICK_SECS));
+
+    loop {
+        ticker.tick().await;
+        let now = Utc::now().timestamp();
+        // removed before delivering, so a crash mid-delivery can't cause a repeat
+        let due: Vec<Reminder> = {
+            let mut list = REMINDERS.lock().await;
+            let (due, pending) = list.reminders.drain(..).partition(|r| r.due <= now);
+            list.reminders = pending;
+            if !due.is_empty() {
+                REMINDERS.persist(&list);
+            }
+            due
+        };
+        if due.is_empty() {
+            continue;
+        }
+
+        let response_type = crate::CONFIG
+            .lock()
+            .await
+            .get_reminder_response_type()
+            .clone();
+        for reminder in due {
+            deliver(
+                &http,
+                &reminder,
+                &response_type,
+                now - reminder.due > LATE_AFTER_SECS,
+            )
+            .await;
+        }
+    }
+}
+
+/// sends a reminder by DM or into the channel it was set in, falling back to
+/// the channel when the user doesn't accept DMs
+async fn deliver(
+    http: &Arc<Http>,
+    reminder: &Reminder,
+    response_type: &CommandResponse,
+    late: bool,
+) {
+    let user_id = UserId(reminder.user_id);
+    let mut content = format!(
+        "⏰ **Reminder:** {}\n*set <t:{}:R> in <#{}>*",
+        reminder.text, reminder.created, reminder.channel_id
+    );
+    if late {
+        content += &format!(
+            "\n*this was due <t:{}:R>, but I was offline then, sorry!*",
+            reminder.due
+        );
+    }
+
+    if *response_type == CommandResponse::Dm {
+        match user_id.create_dm_channel(http).await {
+            Ok(dm) => match say_split(http, dm.id, &content, &[]).await {
+                Ok(_) => return,
+                Err(e) => info!("Unable to dm reminder {}: {}", reminder.id, e),
+            },
+            Err(e) => info!("Unable to dm reminder {}: {}", reminder.id, e),
+        }
+    }
+
+    let content = format!("<@{}> {}", reminder.user_id, content);
+    if let Err(e) = say_split(http, ChannelId(reminder.channel_id), &content, &[]).await {
+        info!("Unable to deliver reminder {}: {}", reminder.id, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2021, 1, 31, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn durations_are_added_to_now() {
+        assert_eq!(
+            parse_when(&["90s", "oven"], Tz::UTC, now()),
+            Some((now() + Duration::seconds(90), 1))
+        );
+        assert_eq!(
+            parse_when(&["1H30m"], Tz::UTC, now()),
+            Some((now() + Duration::minutes(90), 1))
+        );
+        assert_eq!(
+            parse_when(&["1w2d"], Tz::UTC, now()),
+            Some((now() + Duration::days(9), 1))
+        );
+    }
+
+    #[test]
+    fn invalid_durations_are_refused() {
+        for word in &["0m", "10", "m", "10x", "1h30", "-5m", "abc"] {
+            assert_eq!(parse_when(&[word], Tz::UTC, now()), None, "{}", word);
+        }
+    }
+
+    #[test]
+    fn huge_durations_are_refused_without_overflowing() {
+        for word in &[
+            "99999999999999w",
+            "100000000000d",
+            "9223372036854775807s",
+            "99999999999999999999999s",
+            "367d",
+            "300d300d",
+        ] {
+            assert_eq!(parse_when(&[word], Tz::UTC, now()), None, "{}", word);
+        }
+        assert!(parse_when(&["366d"], Tz::UTC, now()).is_some());
+    }
+
+    #[test]
+    fn times_of_day_are_read_in_the_timezone() {
+        let chicago: Tz = "America/Chicago".parse().unwrap();
+        // 18:00 in Chicago is midnight UTC
+        assert_eq!(
+            parse_when(&["18:00"], chicago, now()),
+            Some((Utc.with_ymd_and_hms(2021, 2, 1, 0, 0, 0).unwrap(), 1))
+        );
+        // a time that already passed today means tomorrow
+        assert_eq!(
+            parse_when(&["11:00"], Tz::UTC, now()),
+            Some((Utc.with_ymd_and_hms(2021, 2, 1, 11, 0, 0).unwrap(), 1))
+        );
+    }
+
+    #[test]
+    fn dates_take_two_words() {
+        assert_eq!(
+            parse_when(&["2021-03-01", "18:30", "stream"], Tz::UTC, now()),
+            Some((Utc.with_ymd_and_hms(2021, 3, 1, 18, 30, 0).unwrap(), 2))
+        );
+        assert_eq!(parse_when(&["2021-03-01"], Tz::UTC, now()), None);
+        assert_eq!(parse_when(&[], Tz::UTC, now()), None);
+    }
+}
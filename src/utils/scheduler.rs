@@ -0,0 +1,109 @@
+use crate::prelude::*;
+use crate::utils::{
+    config::ScheduledMessage,
+    discord::post_command,
+    store::{load, save},
+};
+use chrono::{TimeZone, Utc};
+use serde_derive::{Deserialize, Serialize};
+use serenity::{http::Http, model::id::ChannelId};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+/// data file remembering when each schedule last posted
+const SCHEDULE_STATE: &str = "schedule_state.toml";
+
+/// how often schedules are checked
+const TICK_SECS: u64 = 30;
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct ScheduleState {
+    /// unix timestamps keyed by schedule name
+    last_runs: HashMap<String, i64>,
+}
+
+/// Posts scheduled announcements as they fall due. Schedules are read from
+/// `CONFIG` on every tick so that hot reloads apply right away.
+pub async fn run_schedules(http: Arc<Http>) {
+    let mut state: ScheduleState = load(SCHEDULE_STATE).unwrap_or_else(|e| {
+        info!("Unable to read schedule state: {}", e);
+        ScheduleState::default()
+    });
+    let mut ticker = tokio::time::interval(Duration::from_secs(TICK_SECS));
+
+    loop {
+        ticker.tick().await;
+        let now = Utc::now();
+        let schedules = crate::CONFIG.lock().await.get_schedules().clone();
+        let known = state.last_runs.len();
+        state
+            .last_runs
+            .retain(|name, _| schedules.iter().any(|s| s.get_name() == name));
+        let mut changed = state.last_runs.len() != known;
+
+        for schedule in &schedules {
+            // a new schedule first posts at its next due time, not right away
+            let last_run = match state.last_runs.get(schedule.get_name()) {
+                Some(timestamp) => Utc.timestamp_opt(*timestamp, 0).single().unwrap_or(now),
+                None => {
+                    state
+                        .last_runs
+                        .insert(schedule.get_name().to_string(), now.timestamp());
+                    changed = true;
+                    continue;
+                }
+            };
+            let mut due = match schedule.next_after(last_run) {
+                Some(next) if next <= now => next,
+                _ => continue,
+            };
+            // runs missed while the bot was offline are posted once, not one by one
+            while let Some(next) = schedule.next_after(due).filter(|next| *next <= now) {
+                due = next;
+            }
+
+            // the due time is remembered rather than now, so intervals don't drift by a tick,
+            // and before posting, so a crash mid-post can't cause a repost
+            state
+                .last_runs
+                .insert(schedule.get_name().to_string(), due.timestamp());
+            changed = false;
+            if let Err(e) = save(SCHEDULE_STATE, &state) {
+                info!("Unable to save schedule state: {}", e);
+            }
+
+            let cmd = match schedule.get_message() {
+                ScheduledMessage::Command(trigger) => crate::CONFIG
+                    .lock()
+                    .await
+                    .get_commands()
+                    .iter()
+                    .find(|cmd| cmd.get_trigger() == trigger)
+                    .cloned(),
+                ScheduledMessage::Text(_) => None,
+            };
+            for id in schedule.get_channels() {
+                let result = match (schedule.get_message(), &cmd) {
+                    (ScheduledMessage::Text(text), _) => {
+                        say_split(&http, ChannelId(*id), text, &[]).await
+                    }
+                    (ScheduledMessage::Command(_), Some(cmd)) => {
+                        post_command(&http, ChannelId(*id), cmd).await
+                    }
+                    (ScheduledMessage::Command(trigger), None) => {
+                        info!("Schedule command `{}` no longer exists", trigger);
+                        break;
+                    }
+                };
+                if let Err(e) = result {
+                    info!("Schedule `{}` failed in {}: {}", schedule.get_name(), id, e);
+                }
+            }
+        }
+
+        if changed {
+            if let Err(e) = save(SCHEDULE_STATE, &state) {
+                info!("Unable to save schedule state: {}", e);
+            }
+        }
+    }
+}
@@ -0,0 +1,194 @@
+/// Longest message content Discord accepts
+pub const MESSAGE_LIMIT: usize = 2000;
+/// Longest embed field value Discord accepts
+pub const FIELD_LIMIT: usize = 1024;
+/// Fields per embed, keeping each embed below Discord's 6000 character total
+pub const FIELDS_PER_EMBED: usize = 5;
+
+/// room kept free for closing a code block that was split in half
+const FENCE_RESERVE: usize = 4;
+/// longest code block language repeated when a block is reopened
+const MAX_LANGUAGE_LEN: usize = 24;
+
+/// Splits `content` into pieces of at most `limit` characters, preferring
+/// paragraph, then line, then word boundaries. Code blocks that are cut in
+/// half are closed at the end of one piece and reopened in the next.
+pub fn split_message(content: &str, limit: usize) -> Vec<String> {
+    let mut pieces = Vec::new();
+    let mut open_fence: Option<String> = None;
+    let mut remaining = content;
+
+    loop {
+        let prefix = match &open_fence {
+            Some(lang) => format!("```{}\n", lang),
+            None => String::new(),
+        };
+        let prefix_len = prefix.chars().count();
+        if prefix_len + remaining.chars().count() <= limit {
+            if !remaining.is_empty() || pieces.is_empty() {
+                pieces.push(prefix + remaining);
+            }
+            break;
+        }
+
+        let budget = limit.saturating_sub(prefix_len + FENCE_RESERVE).max(1);
+        let window_end = remaining
+            .char_indices()
+            .nth(budget)
+            .map_or(remaining.len(), |(i, _)| i);
+        let window = &remaining[..window_end];
+        let (cut, separator_len) = match window.rfind("\n\n") {
+            Some(i) if i > 0 => (i, 2),
+            _ => match window.rfind('\n') {
+                Some(i) if i > 0 => (i, 1),
+                _ => match window.rfind(' ') {
+                    Some(i) if i > 0 => (i, 1),
+                    _ => (window_end, 0),
+                },
+            },
+        };
+
+        let chunk = &remaining[..cut];
+        open_fence = fence_after(chunk, open_fence);
+        let mut piece = prefix + chunk;
+        if open_fence.is_some() {
+            piece.push_str("\n```");
+        }
+        pieces.push(piece);
+        remaining = &remaining[cut + separator_len..];
+    }
+    pieces
+}
+
+/// Splits `content` into embed field values, grouped into as many embeds as needed.
+pub fn split_embed(content: &str) -> Vec<Vec<String>> {
+    split_message(content, FIELD_LIMIT)
+        .chunks(FIELDS_PER_EMBED)
+        .map(|fields| fields.to_vec())
+        .collect()
+}
+
+/// Returns the language of the code block still open after `chunk`, if any.
+fn fence_after(chunk: &str, mut open_fence: Option<String>) -> Option<String> {
+    for line in chunk.lines() {
+        let line = line.trim_start();
+        if !line.starts_with("```") {
+            continue;
+        }
+        let after = &line[3..];
+        open_fence = match open_fence {
+            Some(_) => None,
+            // a block opened and closed on the same line changes nothing
+            None if after.contains("```") => None,
+            None => Some(fence_language(after.trim_start_matches('`'))),
+        };
+    }
+    open_fence
+}
+
+/// The language of a code block from the text after its opening fence. Text
+/// that isn't a short language name is content, so it isn't repeated.
+fn fence_language(after: &str) -> String {
+    let after = after.trim();
+    let is_language = after.len() <= MAX_LANGUAGE_LEN
+        && after
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_+#.-".contains(c));
+    if is_language {
+        after.to_string()
+    } else {
+        String::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_within(pieces: &[String], limit: usize) {
+        for piece in pieces {
+            assert!(
+                piece.chars().count() <= limit,
+                "{} > {}",
+                piece.len(),
+                limit
+            );
+        }
+    }
+
+    #[test]
+    fn short_content_stays_whole() {
+        assert_eq!(split_message("hi", 10), vec!["hi"]);
+        assert_eq!(split_message("", 10), vec![""]);
+    }
+
+    #[test]
+    fn prefers_paragraphs_then_lines_then_words() {
+        assert_eq!(
+            split_message("aaaa bbbb\n\ncccc dddd", 16),
+            vec!["aaaa bbbb", "cccc dddd"]
+        );
+        assert_eq!(split_message("aaaa\nbbbb cc", 11), vec!["aaaa", "bbbb cc"]);
+        assert_eq!(split_message("aaaa bbbb cc", 9), vec!["aaaa", "bbbb cc"]);
+    }
+
+    #[test]
+    fn long_words_are_cut() {
+        let pieces = split_message(&"x".repeat(25), 10);
+        assert_within(&pieces, 10);
+        assert_eq!(pieces.concat(), "x".repeat(25));
+    }
+
+    #[test]
+    fn split_code_blocks_are_closed_and_reopened() {
+        let content = format!("```rust\n{}\n```", "let x = 1;\n".repeat(10));
+        let pieces = split_message(&content, 50);
+        assert!(pieces.len() > 1);
+        assert_within(&pieces, 50);
+        for piece in &pieces {
+            assert!(piece.starts_with("```rust\n"), "{:?}", piece);
+            assert!(piece.ends_with("```"), "{:?}", piece);
+        }
+    }
+
+    #[test]
+    fn unclosed_fence_with_long_line_isnt_a_language() {
+        let content = format!("```{}", "word ".repeat(400));
+        let pieces = split_message(&content, FIELD_LIMIT);
+        assert_within(&pieces, FIELD_LIMIT);
+        assert!(pieces.len() <= 3, "{} pieces", pieces.len());
+        for piece in &pieces[1..] {
+            assert!(piece.starts_with("```\n"), "{:?}", piece);
+        }
+    }
+
+    #[test]
+    fn fence_language_is_a_short_token() {
+        assert_eq!(fence_language("rust"), "rust");
+        assert_eq!(fence_language(" c++ "), "c++");
+        assert_eq!(fence_language("word word"), "");
+        assert_eq!(fence_language(&"a".repeat(MAX_LANGUAGE_LEN + 1)), "");
+    }
+
+    #[test]
+    fn multi_byte_text_is_split_on_char_boundaries() {
+        let content = "🧁é".repeat(30);
+        let pieces = split_message(&content, 7);
+        assert_within(&pieces, 7);
+        assert_eq!(pieces.concat(), content);
+
+        let content = "ü ".repeat(40);
+        let pieces = split_message(&content, 9);
+        assert_within(&pieces, 9);
+        assert_eq!(pieces.join(" "), content);
+    }
+
+    #[test]
+    fn embed_fields_are_grouped() {
+        let content = "y".repeat(FIELD_LIMIT * 7);
+        let embeds = split_embed(&content);
+        assert_eq!(embeds.len(), 2);
+        assert_eq!(embeds[0].len(), FIELDS_PER_EMBED);
+        assert_eq!(embeds.concat().concat(), content);
+    }
+}
@@ -0,0 +1,164 @@
+use anyhow::Result;
+use once_cell::sync::{Lazy, OnceCell};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+use tokio::sync::{Mutex, MutexGuard};
+use tracing::info;
+
+/// data files that couldn't be read nor moved aside, which `save` won't overwrite
+static UNREADABLE: Lazy<std::sync::Mutex<HashSet<PathBuf>>> = Lazy::new(std::sync::Mutex::default);
+
+/// A data file loaded on first use and written back to disk on every change.
+pub struct Store<T> {
+    file_name: &'static str,
+    data: OnceCell<Mutex<T>>,
+}
+
+impl<T> Store<T> {
+    pub const fn new(file_name: &'static str) -> Self {
+        Self {
+            file_name,
+            data: OnceCell::new(),
+        }
+    }
+}
+
+impl<T: DeserializeOwned + Serialize + Default> Store<T> {
+    /// Locks the data, reading it from disk the first time.
+    pub async fn lock(&self) -> MutexGuard<'_, T> {
+        self.data
+            .get_or_init(|| {
+                Mutex::new(load(self.file_name).unwrap_or_else(|e| {
+                    info!("Unable to read {}: {}", self.file_name, e);
+                    T::default()
+                }))
+            })
+            .lock()
+            .await
+    }
+
+    /// Writes the data back to disk, logging failures.
+    pub fn persist(&self, data: &T) {
+        if let Err(e) = save(self.file_name, data) {
+            info!("Unable to save {}: {}", self.file_name, e);
+        }
+    }
+}
+
+/// Path of a data file kept next to the config at $MUFFETBOT_CONFIG.
+pub fn store_path(file_name: &str) -> PathBuf {
+    match std::env::var("MUFFETBOT_CONFIG") {
+        Ok(config_path) => match std::path::Path::new(&config_path).parent() {
+            Some(dir) => dir.join(file_name),
+            None => PathBuf::from(file_name),
+        },
+        Err(_) => PathBuf::from(file_name),
+    }
+}
+
+/// Reads a data file, falling back to the default if it doesn't exist yet.
+/// A file that can't be read or parsed is moved aside to `<file>.bad` first,
+/// so that saving the default afterwards doesn't overwrite what it held.
+pub fn load<T: DeserializeOwned + Default>(file_name: &str) -> Result<T> {
+    load_path(&store_path(file_name))
+}
+
+fn load_path<T: DeserializeOwned + Default>(path: &Path) -> Result<T> {
+    if !path.exists() {
+        return Ok(T::default());
+    }
+    let e = match read(path) {
+        Ok(data) => return Ok(data),
+        Err(e) => e,
+    };
+
+    let mut bad_path = path.as_os_str().to_owned();
+    bad_path.push(".bad");
+    let bad_path = PathBuf::from(bad_path);
+    match std::fs::rename(path, &bad_path) {
+        Ok(_) => anyhow::bail!("{}, moved it to {}", e, bad_path.display()),
+        Err(rename_error) => {
+            UNREADABLE.lock().unwrap().insert(path.to_path_buf());
+            anyhow::bail!(
+                "{}, and couldn't move it aside ({}) so it won't be saved over",
+                e,
+                rename_error
+            )
+        }
+    }
+}
+
+fn read<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    Ok(toml::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+/// Writes a data file, replacing it only once the new contents are complete.
+pub fn save<T: Serialize>(file_name: &str, data: &T) -> Result<()> {
+    save_path(&store_path(file_name), data)
+}
+
+fn save_path<T: Serialize>(path: &Path, data: &T) -> Result<()> {
+    if UNREADABLE.lock().unwrap().contains(path) {
+        anyhow::bail!("{} couldn't be read, not saving over it", path.display());
+    }
+    let tmp_path = path.with_extension("tmp");
+    std::fs::write(&tmp_path, toml::to_string(data)?)?;
+    std::fs::rename(tmp_path, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_derive::{Deserialize, Serialize};
+
+    #[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+    struct List {
+        items: Vec<u64>,
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("muffet-store-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn missing_file_is_the_default() {
+        let dir = temp_dir("missing");
+        let list: List = load_path(&dir.join("list.toml")).unwrap();
+        assert_eq!(list, List::default());
+    }
+
+    #[test]
+    fn saved_data_loads_back() {
+        let path = temp_dir("roundtrip").join("list.toml");
+        let list = List { items: vec![1, 2] };
+        save_path(&path, &list).unwrap();
+        assert_eq!(load_path::<List>(&path).unwrap(), list);
+    }
+
+    #[test]
+    fn unreadable_file_is_moved_aside() {
+        let dir = temp_dir("corrupt");
+        let path = dir.join("list.toml");
+        std::fs::write(&path, "items = \"not a list\"").unwrap();
+
+        assert!(load_path::<List>(&path).is_err());
+        assert!(!path.exists());
+        assert_eq!(
+            std::fs::read_to_string(dir.join("list.toml.bad")).unwrap(),
+            "items = \"not a list\""
+        );
+
+        // saving afterwards starts a new file and leaves the old contents alone
+        save_path(&path, &List { items: vec![3] }).unwrap();
+        assert_eq!(load_path::<List>(&path).unwrap().items, vec![3]);
+        assert!(dir.join("list.toml.bad").exists());
+    }
+}
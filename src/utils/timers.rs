@@ -0,0 +1,90 @@
+use crate::prelude::*;
+use crate::utils::{config::ScheduledMessage, discord::post_command};
+use once_cell::sync::Lazy;
+use rand::Rng;
+use serenity::{http::Http, model::id::ChannelId};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// how often timers are checked
+const TICK_SECS: u64 = 30;
+
+/// user messages seen in each channel since the bot started
+static CHANNEL_ACTIVITY: Lazy<Mutex<HashMap<u64, u64>>> = Lazy::new(Mutex::default);
+
+/// counts a user message towards the timers of its channel
+pub async fn record_activity(channel_id: ChannelId) {
+    *CHANNEL_ACTIVITY
+        .lock()
+        .await
+        .entry(channel_id.0)
+        .or_insert(0) += 1;
+}
+
+struct TimerState {
+    last_post: Instant,
+    /// channel activity at the last post
+    activity: u64,
+    next: usize,
+}
+
+/// Posts timer messages in rotation, each once its interval has passed and
+/// its channel has seen enough messages since its last post. Timers are read
+/// from `CONFIG` on every tick so that hot reloads apply right away.
+pub async fn run_timers(http: Arc<Http>) {
+    let mut states: HashMap<String, TimerState> = HashMap::new();
+    let mut ticker = tokio::time::interval(Duration::from_secs(TICK_SECS));
+
+    loop {
+        ticker.tick().await;
+        let (timers, commands) = {
+            let config = crate::CONFIG.lock().await;
+            (config.get_timers().clone(), config.get_commands().clone())
+        };
+        states.retain(|key, _| timers.iter().any(|t| &t.key() == key));
+        let activity = CHANNEL_ACTIVITY.lock().await.clone();
+
+        for timer in &timers {
+            let seen = activity.get(&timer.get_channel()).copied().unwrap_or(0);
+            let state = states.entry(timer.key()).or_insert_with(|| TimerState {
+                last_post: Instant::now(),
+                activity: seen,
+                next: 0,
+            });
+            if state.last_post.elapsed() < timer.get_interval()
+                || seen - state.activity < timer.get_min_messages()
+            {
+                continue;
+            }
+
+            let messages = timer.get_messages();
+            let index = if timer.is_random() && messages.len() > 1 {
+                // never the same message twice in a row
+                let offset = rand::thread_rng().gen_range(1..messages.len());
+                (state.next + offset) % messages.len()
+            } else {
+                state.next % messages.len()
+            };
+            state.next = if timer.is_random() { index } else { index + 1 };
+            state.last_post = Instant::now();
+            state.activity = seen;
+
+            let channel_id = ChannelId(timer.get_channel());
+            let result = match &messages[index] {
+                ScheduledMessage::Text(text) => say_split(&http, channel_id, text, &[]).await,
+                ScheduledMessage::Command(trigger) => {
+                    match commands.iter().find(|cmd| cmd.get_trigger() == trigger) {
+                        Some(cmd) => post_command(&http, channel_id, cmd).await,
+                        None => continue,
+                    }
+                }
+            };
+            if let Err(e) = result {
+                info!("Timer failed in {}: {}", channel_id, e);
+            }
+        }
+    }
+}
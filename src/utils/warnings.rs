@@ -0,0 +1,177 @@
+use crate::prelude::*;
+use crate::utils::config::{WarnAction, WarningsData};
+use crate::utils::moderation::{find_role, time_out};
+use crate::utils::store::Store;
+use chrono::Utc;
+use serde_derive::{Deserialize, Serialize};
+use serenity::model::id::{GuildId, UserId};
+
+/// data file holding the warnings and notes moderators left
+const WARNINGS_FILE: &str = "warnings.toml";
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordKind {
+    /// counts toward the warning thresholds and is shown to the member
+    Warning,
+    /// only seen by moderators
+    Note,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Record {
+    id: u64,
+    kind: RecordKind,
+    guild_id: u64,
+    user_id: u64,
+    moderator_id: u64,
+    reason: String,
+    /// unix timestamp
+    timestamp: i64,
+}
+
+impl Record {
+    pub fn get_id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn get_kind(&self) -> RecordKind {
+        self.kind
+    }
+
+    pub fn get_moderator_id(&self) -> u64 {
+        self.moderator_id
+    }
+
+    pub fn get_reason(&self) -> &str {
+        &self.reason
+    }
+
+    pub fn get_timestamp(&self) -> i64 {
+        self.timestamp
+    }
+
+    /// true for warnings too old to count toward the thresholds anymore
+    pub fn is_expired(&self, warnings: &WarningsData) -> bool {
+        warnings
+            .get_expiry()
+            .is_some_and(|expiry| self.timestamp < (Utc::now() - expiry).timestamp())
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct RecordList {
+    next_id: u64,
+    records: Vec<Record>,
+}
+
+static RECORDS: Store<RecordList> = Store::new(WARNINGS_FILE);
+
+/// Stores a warning or note about a member, returning its id.
+pub async fn add_record(
+    kind: RecordKind,
+    guild_id: GuildId,
+    user_id: UserId,
+    moderator_id: UserId,
+    reason: String,
+) -> u64 {
+    let mut list = RECORDS.lock().await;
+    list.next_id += 1;
+    let id = list.next_id;
+    list.records.push(Record {
+        id,
+        kind,
+        guild_id: guild_id.0,
+        user_id: user_id.0,
+        moderator_id: moderator_id.0,
+        reason,
+        timestamp: Utc::now().timestamp(),
+    });
+    RECORDS.persist(&list);
+    id
+}
+
+/// A member's warnings and notes, oldest first.
+pub async fn user_records(guild_id: GuildId, user_id: UserId) -> Vec<Record> {
+    RECORDS
+        .lock()
+        .await
+        .records
+        .iter()
+        .filter(|r| r.guild_id == guild_id.0 && r.user_id == user_id.0)
+        .cloned()
+        .collect()
+}
+
+/// Removes one of a member's warnings, or all of them without an `id`,
+/// returning how many were removed. Notes are kept.
+pub async fn clear_warnings(guild_id: GuildId, user_id: UserId, id: Option<u64>) -> usize {
+    let mut list = RECORDS.lock().await;
+    let before = list.records.len();
+    list.records.retain(|r| {
+        !(r.kind == RecordKind::Warning
+            && r.guild_id == guild_id.0
+            && r.user_id == user_id.0
+            && id.is_none_or(|id| r.id == id))
+    });
+    let removed = before - list.records.len();
+    if removed > 0 {
+        RECORDS.persist(&list);
+    }
+    removed
+}
+
+/// Takes the action of the threshold a member reached with `count` warnings,
+/// returning a description of it for the moderator.
+pub async fn enforce_threshold(
+    ctx: &Context,
+    guild_id: GuildId,
+    user_id: UserId,
+    warnings: &WarningsData,
+    count: usize,
+) -> Option<String> {
+    let threshold = warnings.threshold_for(count)?;
+    let reason = format!("{} warnings", count);
+    let (done, outcome) = match threshold.get_action() {
+        WarnAction::Timeout => {
+            let role = warnings.get_timeout_role().unwrap_or_default();
+            let minutes = threshold.get_timeout().as_secs() / 60;
+            let outcome = match find_role(ctx, guild_id, role).await {
+                Some(role_id) => {
+                    time_out(
+                        &ctx.http,
+                        guild_id,
+                        user_id,
+                        role_id,
+                        threshold.get_timeout(),
+                    )
+                    .await
+                }
+                None => {
+                    return Some(format!(
+                        "I couldn't time them out, the role `{}` doesn't exist",
+                        role
+                    ))
+                }
+            };
+            (format!("timed out for {} minutes", minutes), outcome)
+        }
+        WarnAction::Kick => (
+            String::from("kicked"),
+            guild_id.kick_with_reason(&ctx.http, user_id, &reason).await,
+        ),
+        WarnAction::Ban => (
+            String::from("banned"),
+            guild_id
+                .ban_with_reason(&ctx.http, user_id, 0, &reason)
+                .await,
+        ),
+    };
+    Some(match outcome {
+        Ok(_) => format!("they've been {}", done),
+        Err(e) => {
+            info!("Unable to enforce warning threshold on {}: {}", user_id, e);
+            format!("they should have been {}, but I couldn't: {}", done, e)
+        }
+    })
+}
@@ -0,0 +1,421 @@
+use crate::utils::config::Scrape;
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+use tokio::sync::Mutex;
+
+/// how long a request may take before it is given up on
+const REQUEST_TIMEOUT_SECS: u64 = 20;
+
+/// one client shared by everything fetching from the web, so connections are reused
+static CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .user_agent(concat!("muffetbot/", env!("CARGO_PKG_VERSION")))
+        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        .build()
+        .expect("Unable to build the HTTP client")
+});
+
+/// scraped values and when they were scraped, keyed by the scrape settings
+static SCRAPED: Lazy<Mutex<HashMap<String, (Instant, String)>>> = Lazy::new(Mutex::default);
+
+/// Downloads the body at `url`, failing on any non-success status.
+pub async fn fetch(url: &str) -> Result<Vec<u8>> {
+    let response = CLIENT.get(url).send().await?.error_for_status()?;
+    Ok(response.bytes().await?.to_vec())
+}
+
+/// Finds the http and https links in `text`, including ones wrapped in `<>`
+/// to suppress Discord's preview.
+pub fn find_links(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|word| word.trim_start_matches('<').trim_end_matches('>'))
+        .filter(|word| {
+            reqwest::Url::parse(word)
+                .is_ok_and(|url| url.scheme() == "http" || url.scheme() == "https")
+        })
+        .map(String::from)
+        .collect()
+}
+
+/// What requesting a link ended with
+#[derive(Clone, Debug, PartialEq)]
+pub enum LinkStatus {
+    Ok,
+    /// the link works, but ends up at another url
+    Redirected(String),
+    /// 4xx and 5xx responses
+    Broken(u16),
+    /// timeouts, TLS and connection errors
+    Failed(String),
+}
+
+impl LinkStatus {
+    pub fn is_broken(&self) -> bool {
+        matches!(self, LinkStatus::Broken(_) | LinkStatus::Failed(_))
+    }
+}
+
+impl std::fmt::Display for LinkStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LinkStatus::Ok => write!(f, "ok"),
+            LinkStatus::Redirected(url) => write!(f, "redirects to {}", url),
+            LinkStatus::Broken(status) => write!(f, "responded with {}", status),
+            LinkStatus::Failed(e) => write!(f, "failed: {}", e),
+        }
+    }
+}
+
+/// Requests `url`, following redirects, and reports how it went.
+pub async fn check_link(url: &str) -> LinkStatus {
+    // parsed the way the request normalizes it, so host case and escaping don't count as redirects
+    let mut requested = match reqwest::Url::parse(url) {
+        Ok(requested) => requested,
+        Err(e) => return LinkStatus::Failed(e.to_string()),
+    };
+    requested.set_fragment(None);
+    let response = match CLIENT.get(requested.clone()).send().await {
+        Ok(response) => response,
+        Err(e) if e.is_timeout() => {
+            return LinkStatus::Failed(format!("timed out after {}s", REQUEST_TIMEOUT_SECS))
+        }
+        Err(e) => return LinkStatus::Failed(e.to_string()),
+    };
+
+    let status = response.status();
+    if status.is_client_error() || status.is_server_error() {
+        LinkStatus::Broken(status.as_u16())
+    } else if response.url().as_str().trim_end_matches('/')
+        != requested.as_str().trim_end_matches('/')
+    {
+        LinkStatus::Redirected(response.url().to_string())
+    } else {
+        LinkStatus::Ok
+    }
+}
+
+/// Returns the text or attribute of the first element matching the scrape's
+/// selector, reusing the last result until its ttl runs out. If the page
+/// can't be fetched, an expired result is still better than none.
+pub async fn scrape(settings: &Scrape) -> Result<String> {
+    let key = format!(
+        "{} {} {:?}",
+        settings.get_url(),
+        settings.get_selector(),
+        settings.get_attr()
+    );
+    let cached = SCRAPED.lock().await.get(&key).cloned();
+    if let Some((scraped_at, value)) = &cached {
+        if scraped_at.elapsed() < settings.get_ttl() {
+            return Ok(value.clone());
+        }
+    }
+
+    let value = match fetch(settings.get_url()).await {
+        Ok(body) => extract(settings, &String::from_utf8_lossy(&body))?,
+        Err(e) => match cached {
+            Some((_, value)) => {
+                tracing::info!("Reusing expired scrape of {}: {}", settings.get_url(), e);
+                value
+            }
+            None => return Err(e),
+        },
+    };
+    SCRAPED
+        .lock()
+        .await
+        .insert(key, (Instant::now(), value.clone()));
+    Ok(value)
+}
+
+/// parsed pages can't be held across an await, so extracting stays synchronous
+fn extract(settings: &Scrape, page: &str) -> Result<String> {
+    let selector = match scraper::Selector::parse(settings.get_selector()) {
+        Ok(selector) => selector,
+        Err(e) => anyhow::bail!("invalid selector: {:?}", e),
+    };
+    let document = scraper::Html::parse_document(page);
+    let element = match document.select(&selector).next() {
+        Some(element) => element,
+        None => anyhow::bail!(
+            "nothing matches `{}` on {}",
+            settings.get_selector(),
+            settings.get_url()
+        ),
+    };
+
+    match settings.get_attr() {
+        Some(attr) => match element.value().attr(attr) {
+            // relative links are made absolute so Discord can follow them
+            Some(value) if attr == "href" || attr == "src" => {
+                Ok(reqwest::Url::parse(settings.get_url())?
+                    .join(value)?
+                    .to_string())
+            }
+            Some(value) => Ok(value.to_string()),
+            None => anyhow::bail!("the matched element has no `{}` attribute", attr),
+        },
+        None => Ok(element
+            .text()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")),
+    }
+}
+
+/// seconds link card metadata is reused before the page is fetched again
+const LINK_CARD_TTL_SECS: u64 = 60 * 60;
+
+/// link cards and when they were fetched, keyed by url
+static LINK_CARDS: Lazy<Mutex<HashMap<String, (Instant, LinkCard)>>> = Lazy::new(Mutex::default);
+
+/// OpenGraph and Twitter card metadata of a page
+#[derive(Clone, Debug, Default)]
+pub struct LinkCard {
+    title: Option<String>,
+    description: Option<String>,
+    image: Option<String>,
+    site_name: Option<String>,
+    color: Option<u32>,
+}
+
+impl LinkCard {
+    pub fn get_title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    pub fn get_description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    pub fn get_image(&self) -> Option<&str> {
+        self.image.as_deref()
+    }
+
+    pub fn get_site_name(&self) -> Option<&str> {
+        self.site_name.as_deref()
+    }
+
+    /// The page's `theme-color`, as an RGB value.
+    pub fn get_color(&self) -> Option<u32> {
+        self.color
+    }
+}
+
+/// Returns the link card of the page at `url`, cached for an hour. If the
+/// page can't be fetched, an expired card is still better than none.
+pub async fn link_card(url: &str) -> Result<LinkCard> {
+    let cached = LINK_CARDS.lock().await.get(url).cloned();
+    if let Some((fetched_at, card)) = &cached {
+        if fetched_at.elapsed() < Duration::from_secs(LINK_CARD_TTL_SECS) {
+            return Ok(card.clone());
+        }
+    }
+
+    let card = match fetch(url).await {
+        Ok(body) => parse_link_card(url, &String::from_utf8_lossy(&body))?,
+        Err(e) => match cached {
+            Some((_, card)) => {
+                tracing::info!("Reusing expired link card of {}: {}", url, e);
+                card
+            }
+            None => return Err(e),
+        },
+    };
+    LINK_CARDS
+        .lock()
+        .await
+        .insert(url.to_string(), (Instant::now(), card.clone()));
+    Ok(card)
+}
+
+/// reads the `<meta>` tags of a page, preferring OpenGraph over Twitter card values
+fn parse_link_card(url: &str, page: &str) -> Result<LinkCard> {
+    let document = scraper::Html::parse_document(page);
+    let mut meta: HashMap<String, String> = HashMap::new();
+    let selector = scraper::Selector::parse("meta[content]").expect("valid selector");
+    for element in document.select(&selector) {
+        let element = element.value();
+        let key = match element.attr("property").or_else(|| element.attr("name")) {
+            Some(key) => key.to_lowercase(),
+            None => continue,
+        };
+        if let Some(content) = element.attr("content") {
+            meta.entry(key)
+                .or_insert_with(|| content.trim().to_string());
+        }
+    }
+    let first = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| meta.get(*key))
+            .filter(|value| !value.is_empty())
+            .cloned()
+    };
+
+    let title_selector = scraper::Selector::parse("title").expect("valid selector");
+    let card = LinkCard {
+        title: first(&["og:title", "twitter:title"]).or_else(|| {
+            document
+                .select(&title_selector)
+                .next()
+                .map(|title| title.text().collect::<String>().trim().to_string())
+                .filter(|title| !title.is_empty())
+        }),
+        description: first(&["og:description", "twitter:description", "description"]),
+        // relative images are made absolute so Discord can load them
+        image: first(&[
+            "og:image",
+            "og:image:url",
+            "twitter:image",
+            "twitter:image:src",
+        ])
+        .and_then(|image| reqwest::Url::parse(url).ok()?.join(&image).ok())
+        .map(|image| image.to_string()),
+        site_name: first(&["og:site_name"]),
+        color: first(&["theme-color"]).and_then(|color| parse_hex_color(&color)),
+    };
+
+    if card.title.is_none() && card.description.is_none() && card.image.is_none() {
+        anyhow::bail!("{} has no link card metadata", url);
+    }
+    Ok(card)
+}
+
+/// `#rrggbb` or `#rgb`
+fn parse_hex_color(color: &str) -> Option<u32> {
+    let hex = color.trim().trim_start_matches('#');
+    let hex = match hex.len() {
+        3 => hex.chars().flat_map(|c| vec![c, c]).collect(),
+        6 => hex.to_string(),
+        _ => return None,
+    };
+    u32::from_str_radix(&hex, 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fixture::Fixture;
+
+    const PAGE: &str = r#"<html><body>
+        <a class="title" href="/videos/42">  Latest
+            video </a>
+        <img class="thumb" src="thumbs/42.png" alt="A muffet">
+        </body></html>"#;
+
+    fn settings(url: &str, selector: &str, attr: Option<&str>, ttl_secs: u64) -> Scrape {
+        let attr = attr.map_or(String::new(), |attr| format!("attr = \"{}\"\n", attr));
+        toml::from_str(&format!(
+            "url = \"{}\"\nselector = \"{}\"\n{}ttl_secs = {}",
+            url, selector, attr, ttl_secs
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn extracts_collapsed_text() {
+        let settings = settings("https://example.com/channel", "a.title", None, 0);
+        assert_eq!(extract(&settings, PAGE).unwrap(), "Latest video");
+    }
+
+    #[test]
+    fn resolves_relative_links() {
+        let href = settings("https://example.com/channel/", "a.title", Some("href"), 0);
+        assert_eq!(
+            extract(&href, PAGE).unwrap(),
+            "https://example.com/videos/42"
+        );
+        let src = settings("https://example.com/channel/", "img", Some("src"), 0);
+        assert_eq!(
+            extract(&src, PAGE).unwrap(),
+            "https://example.com/channel/thumbs/42.png"
+        );
+    }
+
+    #[test]
+    fn other_attributes_are_kept_as_is() {
+        let alt = settings("https://example.com", "img.thumb", Some("alt"), 0);
+        assert_eq!(extract(&alt, PAGE).unwrap(), "A muffet");
+    }
+
+    #[test]
+    fn missing_elements_and_attributes_are_errors() {
+        let missing = settings("https://example.com", "h1", None, 0);
+        assert!(extract(&missing, PAGE).is_err());
+        let no_attr = settings("https://example.com", "a.title", Some("title"), 0);
+        assert!(extract(&no_attr, PAGE).is_err());
+    }
+
+    #[tokio::test]
+    async fn link_checks_report_redirects_and_broken_links() {
+        let fixture = Fixture::start();
+        fixture.respond("/page", "hi");
+        fixture.respond_with("/old", 301, &["Location: /page"], "");
+        fixture.respond_with("/broken", 503, &[], "");
+
+        assert_eq!(check_link(&fixture.url("/page")).await, LinkStatus::Ok);
+        assert_eq!(
+            check_link(&fixture.url("/old")).await,
+            LinkStatus::Redirected(fixture.url("/page"))
+        );
+        assert_eq!(
+            check_link(&fixture.url("/broken")).await,
+            LinkStatus::Broken(503)
+        );
+        assert_eq!(
+            check_link(&fixture.url("/missing")).await,
+            LinkStatus::Broken(404)
+        );
+    }
+
+    #[tokio::test]
+    async fn normalized_urls_arent_redirects() {
+        let fixture = Fixture::start();
+        fixture.respond("/", "hi");
+        fixture.respond("/page", "hi");
+        let base = fixture.url("");
+        let upper = base.replace("http://", "HTTP://");
+        assert_eq!(check_link(&upper).await, LinkStatus::Ok);
+        assert_eq!(
+            check_link(&format!("{}/page#top", base)).await,
+            LinkStatus::Ok
+        );
+        assert_eq!(
+            check_link(&format!("{}/./page", base)).await,
+            LinkStatus::Ok
+        );
+    }
+
+    #[tokio::test]
+    async fn scrapes_are_cached_for_their_ttl() {
+        let fixture = Fixture::start();
+        fixture.respond("/page", PAGE);
+        let cached = settings(&fixture.url("/page"), "a.title", Some("href"), 3600);
+        assert_eq!(scrape(&cached).await.unwrap(), fixture.url("/videos/42"));
+
+        fixture.respond("/page", r#"<a class="title" href="/videos/43">Newer</a>"#);
+        assert_eq!(scrape(&cached).await.unwrap(), fixture.url("/videos/42"));
+        let fresh = settings(&fixture.url("/page"), "a.title", None, 0);
+        assert_eq!(scrape(&fresh).await.unwrap(), "Newer");
+    }
+
+    #[tokio::test]
+    async fn expired_scrapes_are_reused_when_the_page_fails() {
+        let fixture = Fixture::start();
+        fixture.respond("/page", PAGE);
+        let expiring = settings(&fixture.url("/page"), "a.title", None, 0);
+        assert_eq!(scrape(&expiring).await.unwrap(), "Latest video");
+
+        fixture.respond_with("/page", 500, &[], "oops");
+        assert_eq!(scrape(&expiring).await.unwrap(), "Latest video");
+
+        let never_scraped = settings(&fixture.url("/gone"), "a", None, 0);
+        assert!(scrape(&never_scraped).await.is_err());
+    }
+}
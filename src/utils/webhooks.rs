@@ -0,0 +1,111 @@
+use crate::prelude::*;
+use crate::utils::config::Persona;
+use crate::utils::split::{split_message, MESSAGE_LIMIT};
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use serenity::{
+    builder::ExecuteWebhook,
+    model::{channel::Channel, id::ChannelId, webhook::Webhook},
+};
+use std::collections::HashMap;
+
+/// name of the webhooks the bot creates and reuses
+const WEBHOOK_NAME: &str = "muffetbot";
+
+/// one managed webhook per channel, so they're only looked up or created once
+static WEBHOOKS: Lazy<Mutex<HashMap<ChannelId, Webhook>>> = Lazy::new(Mutex::default);
+
+/// finds the bot's webhook in a channel, creating it if there is none yet.
+/// Requires the Manage Webhooks permission
+async fn channel_webhook(ctx: &Context, channel_id: ChannelId) -> serenity::Result<Webhook> {
+    let mut webhooks = WEBHOOKS.lock().await;
+    if let Some(webhook) = webhooks.get(&channel_id) {
+        return Ok(webhook.clone());
+    }
+
+    let bot_id = ctx.cache.current_user_id().await;
+    let existing = channel_id.webhooks(&ctx.http).await?.into_iter().find(|w| {
+        w.name.as_deref() == Some(WEBHOOK_NAME) && w.user.as_ref().map(|u| u.id) == Some(bot_id)
+    });
+    let webhook = match existing {
+        Some(webhook) => webhook,
+        None => match channel_id.to_channel(ctx).await? {
+            Channel::Guild(channel) => channel.create_webhook(&ctx.http, WEBHOOK_NAME).await?,
+            _ => {
+                return Err(serenity::Error::Other(
+                    "webhooks only exist in server channels",
+                ))
+            }
+        },
+    };
+
+    webhooks.insert(channel_id, webhook.clone());
+    Ok(webhook)
+}
+
+/// executes the channel's webhook once per payload, posting as the persona
+async fn execute_as<F>(
+    ctx: &Context,
+    channel_id: ChannelId,
+    persona: &Persona,
+    payloads: usize,
+    mut fill: F,
+) -> serenity::Result<Vec<Message>>
+where
+    F: FnMut(usize, &mut ExecuteWebhook),
+{
+    let webhook = channel_webhook(ctx, channel_id).await?;
+
+    let mut sent = Vec::new();
+    for i in 0..payloads {
+        let result = webhook
+            .execute(&ctx.http, true, |w| {
+                w.username(persona.get_name());
+                if let Some(avatar) = persona.get_avatar() {
+                    w.avatar_url(avatar);
+                }
+                fill(i, w);
+                w
+            })
+            .await;
+        match result {
+            Ok(message) => sent.extend(message),
+            Err(e) => {
+                // someone deleted the webhook, it is recreated next time
+                if is_not_found(&e) {
+                    WEBHOOKS.lock().await.remove(&channel_id);
+                }
+                return Err(e);
+            }
+        }
+    }
+    Ok(sent)
+}
+
+/// posts `content` into the channel under the persona's name and avatar,
+/// split into as many messages as needed
+pub async fn persona_say(
+    ctx: &Context,
+    channel_id: ChannelId,
+    persona: &Persona,
+    content: &str,
+) -> serenity::Result<Vec<Message>> {
+    let pieces = split_message(content, MESSAGE_LIMIT);
+    execute_as(ctx, channel_id, persona, pieces.len(), |i, w| {
+        w.content(&pieces[i]);
+    })
+    .await
+}
+
+/// posts each embed, built with `Embed::fake`, as its own message under the persona's name and avatar
+pub async fn persona_embeds(
+    ctx: &Context,
+    channel_id: ChannelId,
+    persona: &Persona,
+    embeds: Vec<Value>,
+) -> serenity::Result<Vec<Message>> {
+    execute_as(ctx, channel_id, persona, embeds.len(), |i, w| {
+        w.embeds(vec![embeds[i].clone()]);
+    })
+    .await
+}
@@ -0,0 +1,224 @@
+use crate::prelude::*;
+use crate::utils::config::WordAction;
+use crate::utils::moderation::{find_role, is_exempt, log_filtered, strip_invisible, time_out};
+use once_cell::sync::Lazy;
+use regex::{Regex, RegexBuilder};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// when each guild member, by guild and user id, was caught by the word filter
+type Strikes = HashMap<(u64, u64), Vec<Instant>>;
+
+/// strikes within the strike window
+static STRIKES: Lazy<Mutex<Strikes>> = Lazy::new(Mutex::default);
+
+/// Matches any of `words` as whole words, ignoring case, with any run of
+/// whitespace standing in for the spaces in phrases.
+pub fn word_list_regex(words: &[String]) -> anyhow::Result<Regex> {
+    let alternatives: Vec<String> = words
+        .iter()
+        .map(|word| {
+            word.split_whitespace()
+                .map(regex::escape)
+                .collect::<Vec<String>>()
+                .join(r"\s+")
+        })
+        .collect();
+    // `\b` would never match next to terms that start or end with a symbol
+    Ok(RegexBuilder::new(&format!(
+        r"(?:^|[^\p{{L}}\p{{N}}_])(?:{})(?:$|[^\p{{L}}\p{{N}}_])",
+        alternatives.join("|")
+    ))
+    .case_insensitive(true)
+    .build()?)
+}
+
+/// Raises `action` to the step of `escalation` for a member's `strikes`th
+/// strike within the window. Strikes past the end repeat the last step.
+pub fn escalate(escalation: &[WordAction], action: WordAction, strikes: usize) -> WordAction {
+    match escalation
+        .get(strikes.saturating_sub(1))
+        .or_else(|| escalation.last())
+    {
+        Some(step) => action.max(*step),
+        None => action,
+    }
+}
+
+/// Records a strike against a member, returning how many they have within `window`.
+async fn strike(guild_id: u64, user_id: u64, window: Duration) -> usize {
+    let mut strikes = STRIKES.lock().await;
+    // members without recent strikes are dropped so the map doesn't grow forever
+    strikes.retain(|_, times| {
+        times.retain(|time| time.elapsed() < window);
+        !times.is_empty()
+    });
+    let times = strikes.entry((guild_id, user_id)).or_default();
+    times.push(Instant::now());
+    times.len()
+}
+
+/// Applies the word filter to a chat message. Returns true if the message was caught.
+#[instrument]
+pub async fn filter_words(ctx: &Context, msg: &Message) -> bool {
+    let guild_id = match msg.guild_id {
+        Some(guild_id) => guild_id,
+        None => return false,
+    };
+    let filter = match crate::CONFIG.lock().await.get_word_filter() {
+        Some(filter) if filter.watches(msg.channel_id.0) => filter.clone(),
+        _ => return false,
+    };
+    let action = match filter.check(&strip_invisible(&msg.content)) {
+        Some(action) => action,
+        None => return false,
+    };
+    if is_exempt(ctx, msg, guild_id, filter.get_exempt_roles()).await {
+        return false;
+    }
+
+    let strikes = strike(guild_id.0, msg.author.id.0, filter.get_strike_window()).await;
+    let action = escalate(filter.get_escalation(), action, strikes);
+    if let Err(e) = msg.delete(ctx).await {
+        info!("Word filter couldn't delete a message: {}", e);
+    }
+
+    let consequence = match action {
+        WordAction::Delete | WordAction::Warn => String::new(),
+        WordAction::Timeout => format!(
+            " You've been timed out for {} minutes.",
+            filter.get_timeout().as_secs() / 60
+        ),
+        WordAction::Kick => String::from(" You've been kicked from the server."),
+    };
+    // sent before kicking, since the bot can't DM people it shares no server with
+    if action >= WordAction::Warn {
+        let warning = format!(
+            "Your message in <#{}> was removed because it contains a filtered word.{}",
+            msg.channel_id, consequence
+        );
+        if let Err(e) = msg.author.direct_message(ctx, |m| m.content(warning)).await {
+            info!("Word filter warning failed: {}", e);
+        }
+    }
+
+    let outcome = match action {
+        WordAction::Delete | WordAction::Warn => Ok(()),
+        WordAction::Timeout => {
+            let role = filter.get_timeout_role().unwrap_or_default();
+            match find_role(ctx, guild_id, role).await {
+                Some(role_id) => {
+                    time_out(
+                        &ctx.http,
+                        guild_id,
+                        msg.author.id,
+                        role_id,
+                        filter.get_timeout(),
+                    )
+                    .await
+                }
+                None => {
+                    info!("Word filter timeout role `{}` doesn't exist", role);
+                    Ok(())
+                }
+            }
+        }
+        WordAction::Kick => {
+            guild_id
+                .kick_with_reason(&ctx.http, msg.author.id, "word filter")
+                .await
+        }
+    };
+    if let Err(e) = outcome {
+        info!(
+            "Word filter couldn't {:?} {}: {}",
+            action,
+            msg.author.tag(),
+            e
+        );
+    }
+
+    if let Some(log_channel) = filter.get_log_channel() {
+        let reason = format!(
+            "strike {} within {} minutes, {:?}",
+            strikes,
+            filter.get_strike_window().as_secs() / 60,
+            action
+        )
+        .to_lowercase();
+        log_filtered(ctx, log_channel, "🚫", "removed", msg, &reason).await;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matcher(words: &[&str]) -> Regex {
+        let words: Vec<String> = words.iter().map(|word| word.to_string()).collect();
+        word_list_regex(&words).unwrap()
+    }
+
+    #[test]
+    fn words_match_whole_words_only() {
+        let regex = matcher(&["bad"]);
+        assert!(regex.is_match("bad"));
+        assert!(regex.is_match("that's bad!"));
+        assert!(regex.is_match("(bad)"));
+        assert!(!regex.is_match("badge"));
+        assert!(!regex.is_match("sinbad"));
+        assert!(!regex.is_match("bad_word"));
+    }
+
+    #[test]
+    fn phrases_match_across_any_whitespace() {
+        let regex = matcher(&["free  nitro"]);
+        assert!(regex.is_match("get free nitro here"));
+        assert!(regex.is_match("free\n\tnitro"));
+        assert!(!regex.is_match("freenitro"));
+        assert!(!regex.is_match("free the nitro"));
+    }
+
+    #[test]
+    fn terms_can_start_or_end_with_symbols() {
+        let regex = matcher(&["$scam", "c++", "@here"]);
+        assert!(regex.is_match("buy $scam now"));
+        assert!(regex.is_match("I like c++."));
+        assert!(regex.is_match("@here"));
+        assert!(!regex.is_match("buy$scam"));
+        assert!(!regex.is_match("c++x"));
+        // regex syntax in words is matched literally
+        assert!(!regex.is_match("cc"));
+    }
+
+    #[test]
+    fn words_ignore_case() {
+        let regex = matcher(&["Free Nitro", "ÄRGER"]);
+        assert!(regex.is_match("FREE NITRO"));
+        assert!(regex.is_match("free nitro"));
+        assert!(regex.is_match("so much ärger"));
+    }
+
+    #[test]
+    fn escalation_follows_strikes() {
+        use WordAction::*;
+        let steps = [Delete, Warn, Timeout, Kick];
+        assert_eq!(escalate(&steps, Delete, 1), Delete);
+        assert_eq!(escalate(&steps, Delete, 2), Warn);
+        assert_eq!(escalate(&steps, Delete, 3), Timeout);
+        assert_eq!(escalate(&steps, Delete, 4), Kick);
+        // strikes past the end repeat the last step
+        assert_eq!(escalate(&steps, Delete, 9), Kick);
+    }
+
+    #[test]
+    fn escalation_never_softens_the_action() {
+        use WordAction::*;
+        assert_eq!(escalate(&[Delete, Warn], Timeout, 1), Timeout);
+        assert_eq!(escalate(&[Delete, Warn], Timeout, 5), Timeout);
+        assert_eq!(escalate(&[], Warn, 3), Warn);
+    }
+}